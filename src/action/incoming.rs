@@ -1,310 +1,625 @@
 use crate::config::Config;
+use crate::error::{Diagnostic, DiagnosticKind, IncomingError};
 use crate::formatter::incoming_type::{
+    IncomingMessage,
+    account::{AccountAlert, AccountUpdate, PhoneNumberQualityUpdate},
+    ads::{Ads, Referral, TextBody, WelcomeMessage},
     audio::{Audio, AudioData},
-    button::{Button, ButtonDetails, MsgBtnContext},
-    contact::{Address, Contact, Email, Name, Organization, Phone, Url},
+    button::{Button, ButtonDetails},
+    contact::{Address, Contact, Contacts, Email, Name, Organization, Phone, Url},
+    context::Context,
     document::{Document, DocumentData},
-    full_messages_payload::WebhookMessagePayload,
-    full_statuses_payload::WebhookStatusesPayload,
+    enquiry::{Enquiry, EnquiryPayload, ReferredProduct},
+    event::{EventPayload, WebhookEvent},
+    full_messages_payload::{self, Vaalue},
     image::{Image, ImageData},
+    interactive::{ButtonReply, FlowReply, Interactive, InteractiveReply, ListReply},
     location::{Location, LocationPayload},
-    order::{MessageContext, Order, OrderDetails, ProductItem},
+    order::{Order, OrderDetails, ProductItem},
+    other::Other,
     reaction::{Reaction, ReactionPayload},
+    statuses::StatusUpdate,
     sticker::{Sticker, StickerData},
+    system::{Identity, System, SystemPayload},
+    template::{TemplateCategoryUpdate, TemplateQualityUpdate, TemplateStatusUpdate},
     text::{Text, TextPayload},
+    timestamp::Timestamp,
     unknown::{ErrorDetail, Unknown},
     video::{Video, VideoData},
+    webhook_payload::WebhookPayload,
 };
-use serde_json::{Value, json};
-
-pub fn find_messages(config: Config, message: &str) -> Result<(Vec<Value>, Vec<String>), String> {
-    let mut error_resp: Vec<String> = Vec::new();
-    let mut success_resp: Vec<Value> = Vec::new();
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_path_to_error::Segment;
+
+/// Field name of webhook changes that carry messages, statuses and errors.
+const MESSAGES_FIELD: &str = "messages";
+
+/// Parses any WhatsApp webhook payload into an ordered list of events.
+///
+/// The envelope is deserialized once. Each change is turned into events in payload order:
+/// for the `messages` field, its messages, then statuses, then errors; any other field is
+/// yielded as-is with its raw `value`. A change whose `value` cannot be parsed is reported
+/// as a diagnostic, and the other changes are still returned.
+///
+/// # Arguments
+///
+/// * `config` - Used to reject entries and phone numbers that do not belong to this account.
+/// * `payload` - Raw JSON string received from the WhatsApp webhook.
+///
+/// # Returns
+///
+/// A `Result` with a tuple of the events and the diagnostics for skipped items,
+/// or an `IncomingError` if the payload is not a valid webhook.
+pub fn find_events(
+    config: &Config,
+    payload: &str,
+) -> Result<(Vec<WebhookEvent>, Vec<Diagnostic>), IncomingError> {
+    collect_events(
+        payload,
+        |business_id| business_id == config.whatsapp_business_id,
+        |_, phone_number_id| phone_number_id == config.whatsapp_phone_number_id,
+    )
+}
 
-    let messages: WebhookMessagePayload = serde_json::from_str(message).unwrap();
+/// Parses any WhatsApp webhook payload, keeping only the accounts accepted by the given checks.
+///
+/// # Arguments
+///
+/// * `payload` - Raw JSON string received from the WhatsApp webhook.
+/// * `is_known_business` - Whether an entry id belongs to a known business account.
+/// * `is_known_phone_number` - Whether a `(business id, phone_number_id)` pair is known.
+///
+/// # Returns
+///
+/// The same as `find_events`.
+pub(crate) fn collect_events(
+    payload: &str,
+    is_known_business: impl Fn(&str) -> bool,
+    is_known_phone_number: impl Fn(&str, &str) -> bool,
+) -> Result<(Vec<WebhookEvent>, Vec<Diagnostic>), IncomingError> {
+    let mut error_resp: Vec<Diagnostic> = Vec::new();
+    let mut success_resp: Vec<WebhookEvent> = Vec::new();
+
+    let payload: WebhookPayload = parse_payload(payload)?;
 
-    for (entry_index, entry) in messages.entry.iter().enumerate() {
-        if entry.id.trim().trim_matches('"') != config.whatsapp_business_id {
-            error_resp.push(format!("Entry id not recognized index: {}", entry_index));
+    for (entry_index, entry) in payload.entry.iter().enumerate() {
+        let entry_id = entry.id.trim().trim_matches('"');
+        if !is_known_business(entry_id) {
+            error_resp.push(Diagnostic {
+                path: format!("entry[{}]", entry_index),
+                entry_index,
+                change_index: None,
+                item_index: None,
+                kind: DiagnosticKind::UnknownBusinessAccount(entry_id.to_string()),
+            });
             continue;
         }
         for (change_index, change) in entry.changes.iter().enumerate() {
-            if change
-                .value
-                .metadata
-                .phone_number_id
-                .trim()
-                .trim_matches('"')
-                != config.whatsapp_phone_number_id
-            {
-                error_resp.push(format!(
-                    "Metadata phone_number_id not recognized index: {}",
-                    change_index
-                ));
+            let path = format!("entry[{}].changes[{}]", entry_index, change_index);
+
+            if change.field != MESSAGES_FIELD {
+                match parse_field(&change.field, &change.value) {
+                    Ok(payload) => success_resp.push(WebhookEvent {
+                        entry_id: entry_id.to_string(),
+                        field: change.field.clone(),
+                        metadata: None,
+                        payload,
+                    }),
+                    Err(error) => error_resp.push(Diagnostic {
+                        path: format!("{}.value", path),
+                        entry_index,
+                        change_index: Some(change_index),
+                        item_index: None,
+                        kind: DiagnosticKind::Invalid(error.to_string()),
+                    }),
+                }
                 continue;
             }
-            for message in change.value.messages.iter() {
-                if let Some(r#type) = message.get("type") {
-                    if r#type == "order" {
-                        let order = Order {
-                            from: message
-                                .get("from")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            id: message
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            context: {
-                                let context =
-                                    message.get("context").unwrap_or(&serde_json::Value::Null);
-                                MessageContext {
-                                    from: context
-                                        .get("from")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    id: context
-                                        .get("id")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                }
-                            },
 
-                            r#type: message
-                                .get("type")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            timestamp: message
-                                .get("timestamp")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            order: {
-                                let order_details =
-                                    message.get("order").unwrap_or(&serde_json::Value::Null);
-                                OrderDetails {
-                                    catalog_id: order_details
-                                        .get("catalog_id")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
+            let value: Vaalue = match serde_path_to_error::deserialize(&change.value) {
+                Ok(value) => value,
+                Err(error) => {
+                    let value_path = match error.path().to_string().as_str() {
+                        "." => format!("{}.value", path),
+                        inner => format!("{}.value.{}", path, inner),
+                    };
+                    error_resp.push(Diagnostic {
+                        path: value_path,
+                        entry_index,
+                        change_index: Some(change_index),
+                        item_index: None,
+                        kind: DiagnosticKind::Invalid(error.into_inner().to_string()),
+                    });
+                    continue;
+                }
+            };
+
+            let phone_number_id = value.metadata.phone_number_id.trim().trim_matches('"');
+            if !is_known_phone_number(entry_id, phone_number_id) {
+                error_resp.push(Diagnostic {
+                    path,
+                    entry_index,
+                    change_index: Some(change_index),
+                    item_index: None,
+                    kind: DiagnosticKind::UnknownPhoneNumber(phone_number_id.to_string()),
+                });
+                continue;
+            }
 
-                                    product_items: {
-                                        let product_items = order_details
-                                            .get("product_items")
-                                            .unwrap_or(&serde_json::Value::Null);
-                                        product_items
-                                            .as_array()
-                                            .unwrap_or(&vec![])
-                                            .iter()
-                                            .map(|item| ProductItem {
-                                                product_retailer_id: item
-                                                    .get("product_retailer_id")
-                                                    .and_then(|v| v.as_str())
-                                                    .unwrap_or_default()
-                                                    .to_string(),
-
-                                                quantity: item
-                                                    .get("quantity")
-                                                    .and_then(|v| v.as_str())
-                                                    .unwrap_or_default()
-                                                    .to_string(),
-
-                                                item_price: item
-                                                    .get("item_price")
-                                                    .and_then(|v| v.as_str())
-                                                    .unwrap_or_default()
-                                                    .to_string(),
-
-                                                currency: item
-                                                    .get("currency")
-                                                    .and_then(|v| v.as_str())
-                                                    .unwrap_or_default()
-                                                    .to_string(),
-                                            })
-                                            .collect::<Vec<_>>()
-                                    },
-
-                                    text: order_details
-                                        .get("text")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                }
-                            },
-                        };
+            let event = |payload| WebhookEvent {
+                entry_id: entry_id.to_string(),
+                field: change.field.clone(),
+                metadata: Some(value.metadata.clone()),
+                payload,
+            };
+
+            for (message_index, message) in value.messages.iter().enumerate() {
+                match parse_message(message, &value) {
+                    Ok(message) => success_resp.push(event(EventPayload::Message(message))),
+                    Err(kind) => error_resp.push(Diagnostic {
+                        path: format!("{}.value.messages[{}]", path, message_index),
+                        entry_index,
+                        change_index: Some(change_index),
+                        item_index: Some(message_index),
+                        kind,
+                    }),
+                }
+            }
+            for (status_index, status) in value.statuses.iter().enumerate() {
+                match StatusUpdate::deserialize(status) {
+                    Ok(status) => success_resp.push(event(EventPayload::Status(status))),
+                    Err(error) => error_resp.push(Diagnostic {
+                        path: format!("{}.value.statuses[{}]", path, status_index),
+                        entry_index,
+                        change_index: Some(change_index),
+                        item_index: Some(status_index),
+                        kind: DiagnosticKind::Invalid(error.to_string()),
+                    }),
+                }
+            }
+            for error in value.errors.iter() {
+                success_resp.push(event(EventPayload::Error(parse_error_detail(error))));
+            }
+        }
+    }
 
-                        let payload = json!(order);
+    Ok((success_resp, error_resp))
+}
 
-                        success_resp.push(payload)
-                    } else if r#type == "text" {
-                        let text_message = Text {
-                            from: message
-                                .get("from")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
+/// Parses a webhook payload and keeps only the incoming messages.
+pub fn find_messages(
+    config: &Config,
+    payload: &str,
+) -> Result<(Vec<IncomingMessage>, Vec<Diagnostic>), IncomingError> {
+    let (events, diagnostics) = find_events(config, payload)?;
+    let messages = events
+        .into_iter()
+        .filter_map(|event| match event.payload {
+            EventPayload::Message(message) => Some(message),
+            _ => None,
+        })
+        .collect();
+
+    Ok((messages, diagnostics))
+}
 
-                            id: message
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
+/// Parses a webhook payload and keeps only the message status updates.
+pub fn find_statuses(
+    config: &Config,
+    payload: &str,
+) -> Result<(Vec<StatusUpdate>, Vec<Diagnostic>), IncomingError> {
+    let (events, diagnostics) = find_events(config, payload)?;
+    let statuses = events
+        .into_iter()
+        .filter_map(|event| match event.payload {
+            EventPayload::Status(status) => Some(status),
+            _ => None,
+        })
+        .collect();
+
+    Ok((statuses, diagnostics))
+}
 
-                            timestamp: message
-                                .get("timestamp")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
+/// Converts the `value` of a change other than `messages` into a typed event.
+///
+/// Fields this crate does not model are returned as `EventPayload::Other`.
+fn parse_field(field: &str, value: &Value) -> Result<EventPayload, serde_json::Error> {
+    let payload = match field {
+        "message_template_status_update" => {
+            EventPayload::TemplateStatus(TemplateStatusUpdate::deserialize(value)?)
+        }
+        "message_template_quality_update" => {
+            EventPayload::TemplateQuality(TemplateQualityUpdate::deserialize(value)?)
+        }
+        "template_category_update" => {
+            EventPayload::TemplateCategory(TemplateCategoryUpdate::deserialize(value)?)
+        }
+        "phone_number_quality_update" => {
+            EventPayload::PhoneNumberQuality(PhoneNumberQualityUpdate::deserialize(value)?)
+        }
+        "account_update" => EventPayload::AccountUpdate(AccountUpdate::deserialize(value)?),
+        "account_alerts" => EventPayload::AccountAlert(AccountAlert::deserialize(value)?),
+        _ => EventPayload::Other(value.clone()),
+    };
 
-                            r#type: message
-                                .get("type")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
+    Ok(payload)
+}
 
-                            text: TextPayload {
-                                body: message
-                                    .get("text")
-                                    .and_then(|text_obj| text_obj.get("body"))
+/// Converts a single entry of `value.messages` into a typed message.
+///
+/// `value` is the change the message came from; its `contacts` and `metadata` are
+/// attached to the message.
+fn parse_message(message: &Value, value: &Vaalue) -> Result<IncomingMessage, DiagnosticKind> {
+    let Some(r#type) = message.get("type") else {
+        return Err(DiagnosticKind::MissingType);
+    };
+
+    if r#type == "order" {
+        let order = Order {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            order: {
+                let order_details = message.get("order").unwrap_or(&serde_json::Value::Null);
+                OrderDetails {
+                    catalog_id: order_details
+                        .get("catalog_id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    product_items: {
+                        let product_items = order_details
+                            .get("product_items")
+                            .unwrap_or(&serde_json::Value::Null);
+                        product_items
+                            .as_array()
+                            .unwrap_or(&vec![])
+                            .iter()
+                            .map(|item| ProductItem {
+                                product_retailer_id: item
+                                    .get("product_retailer_id")
                                     .and_then(|v| v.as_str())
                                     .unwrap_or_default()
                                     .to_string(),
-                            },
-                        };
-
-                        let payload = json!(text_message);
-
-                        success_resp.push(payload);
-                    } else if r#type == "unknown" {
-                        let unknown_message = Unknown {
-                            from: message
-                                .get("from")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            id: message
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            timestamp: message
-                                .get("timestamp")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            r#type: message
-                                .get("type")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            errors: message
-                                .get("errors")
-                                .and_then(|v| v.as_array())
-                                .map(|arr| {
-                                    arr.iter()
-                                        .map(|err| {
-                                            ErrorDetail {
-                                                code: err
-                                                    .get("code")
-                                                    .and_then(|v| v.as_u64())
-                                                    .unwrap_or(0)
-                                                    as u32, // assuming `code` is u32
-
-                                                details: err
-                                                    .get("details")
-                                                    .and_then(|v| v.as_str())
-                                                    .unwrap_or_default()
-                                                    .to_string(),
-
-                                                title: err
-                                                    .get("title")
-                                                    .and_then(|v| v.as_str())
-                                                    .unwrap_or_default()
-                                                    .to_string(),
-                                            }
-                                        })
-                                        .collect()
-                                })
-                                .unwrap_or_else(Vec::new),
-                        };
-
-                        let payload = json!(unknown_message);
-
-                        success_resp.push(payload);
-                    } else if r#type == "location" {
-                        let location_message = Location {
-                            from: message
-                                .get("from")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            id: message
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
 
-                            timestamp: message
-                                .get("timestamp")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            location: {
-                                let loc =
-                                    message.get("location").unwrap_or(&serde_json::Value::Null);
-                                LocationPayload {
-                                    latitude: loc
-                                        .get("latitude")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    longitude: loc
-                                        .get("longitude")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    name: loc
-                                        .get("name")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    address: loc
-                                        .get("address")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                }
-                            },
-                        };
+                                quantity: item
+                                    .get("quantity")
+                                    .and_then(|v| v.as_str())
+                                    .unwrap_or_default()
+                                    .to_string(),
 
-                        let payload = json!(location_message);
+                                item_price: item
+                                    .get("item_price")
+                                    .and_then(|v| v.as_str())
+                                    .unwrap_or_default()
+                                    .to_string(),
 
-                        success_resp.push(payload);
-                    } else if r#type == "contacts" {
-                        let contacts_message = Contact {
+                                currency: item
+                                    .get("currency")
+                                    .and_then(|v| v.as_str())
+                                    .unwrap_or_default()
+                                    .to_string(),
+                            })
+                            .collect::<Vec<_>>()
+                    },
+
+                    text: order_details
+                        .get("text")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                }
+            },
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::Order(order))
+    } else if r#type == "text"
+        && message
+            .get("context")
+            .and_then(|context| context.get("referred_product"))
+            .is_some()
+    {
+        let enquiry_message = Enquiry {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            text: EnquiryPayload {
+                body: message
+                    .get("text")
+                    .and_then(|text_obj| text_obj.get("body"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            },
+
+            context: parse_context(message).unwrap_or_default(),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::Enquiry(enquiry_message))
+    } else if r#type == "text" && message.get("referral").is_some() {
+        let ads_message = Ads {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            text: TextBody {
+                body: message
+                    .get("text")
+                    .and_then(|text_obj| text_obj.get("body"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            },
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            referral: parse_referral(message).unwrap_or_default(),
+        };
+
+        Ok(IncomingMessage::Ads(ads_message))
+    } else if r#type == "text" {
+        let text_message = Text {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            text: TextPayload {
+                body: message
+                    .get("text")
+                    .and_then(|text_obj| text_obj.get("body"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            },
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::Text(text_message))
+    } else if r#type == "unknown" || r#type == "unsupported" {
+        let unknown_message = Unknown {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            errors: message
+                .get("errors")
+                .and_then(|v| v.as_array())
+                .map(|arr| arr.iter().map(parse_error_detail).collect())
+                .unwrap_or_default(),
+
+            raw: message.clone(),
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::Unknown(unknown_message))
+    } else if r#type == "location" {
+        let location_message = Location {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            location: {
+                let loc = message.get("location").unwrap_or(&serde_json::Value::Null);
+                let (Some(latitude), Some(longitude)) = (
+                    loc.get("latitude").and_then(parse_coordinate),
+                    loc.get("longitude").and_then(parse_coordinate),
+                ) else {
+                    return Err(DiagnosticKind::Invalid(
+                        "location has no valid latitude and longitude".to_string(),
+                    ));
+                };
+                LocationPayload {
+                    latitude,
+
+                    longitude,
+
+                    name: loc
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+
+                    address: loc
+                        .get("address")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+
+                    url: loc
+                        .get("url")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+                }
+            },
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::Location(location_message))
+    } else if r#type == "contacts" {
+        let contacts_message = Contacts {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("contacts")
+                .to_string(),
+
+            contacts: message
+                .get("contacts")
+                .and_then(|v| v.as_array())
+                .map(|contacts| {
+                    contacts
+                        .iter()
+                        .map(|contact| Contact {
                             addresses: {
                                 let addresses =
-                                    message.get("addresses").unwrap_or(&serde_json::Value::Null);
+                                    contact.get("addresses").unwrap_or(&serde_json::Value::Null);
                                 addresses
                                     .as_array()
                                     .unwrap_or(&vec![])
@@ -355,7 +670,7 @@ pub fn find_messages(config: Config, message: &str) -> Result<(Vec<Value>, Vec<S
                                     .collect::<Vec<_>>()
                             },
 
-                            birthday: message
+                            birthday: contact
                                 .get("birthday")
                                 .and_then(|v| v.as_str())
                                 .unwrap_or_default()
@@ -363,7 +678,7 @@ pub fn find_messages(config: Config, message: &str) -> Result<(Vec<Value>, Vec<S
 
                             emails: {
                                 let emails =
-                                    message.get("emails").unwrap_or(&serde_json::Value::Null);
+                                    contact.get("emails").unwrap_or(&serde_json::Value::Null);
                                 emails
                                     .as_array()
                                     .unwrap_or(&vec![])
@@ -385,7 +700,7 @@ pub fn find_messages(config: Config, message: &str) -> Result<(Vec<Value>, Vec<S
                             },
 
                             name: {
-                                let name = message.get("name").unwrap_or(&serde_json::Value::Null);
+                                let name = contact.get("name").unwrap_or(&serde_json::Value::Null);
                                 Name {
                                     formatted_name: name
                                         .get("formatted_name")
@@ -426,7 +741,7 @@ pub fn find_messages(config: Config, message: &str) -> Result<(Vec<Value>, Vec<S
                             },
 
                             org: {
-                                let org = message.get("org").unwrap_or(&serde_json::Value::Null);
+                                let org = contact.get("org").unwrap_or(&serde_json::Value::Null);
                                 Organization {
                                     company: org
                                         .get("company")
@@ -450,7 +765,7 @@ pub fn find_messages(config: Config, message: &str) -> Result<(Vec<Value>, Vec<S
 
                             phones: {
                                 let phones =
-                                    message.get("phones").unwrap_or(&serde_json::Value::Null);
+                                    contact.get("phones").unwrap_or(&serde_json::Value::Null);
                                 phones
                                     .as_array()
                                     .unwrap_or(&vec![])
@@ -478,7 +793,7 @@ pub fn find_messages(config: Config, message: &str) -> Result<(Vec<Value>, Vec<S
                             },
 
                             urls: {
-                                let urls = message.get("urls").unwrap_or(&serde_json::Value::Null);
+                                let urls = contact.get("urls").unwrap_or(&serde_json::Value::Null);
                                 urls.as_array()
                                     .unwrap_or(&vec![])
                                     .iter()
@@ -497,1421 +812,895 @@ pub fn find_messages(config: Config, message: &str) -> Result<(Vec<Value>, Vec<S
                                     })
                                     .collect::<Vec<_>>()
                             },
-                        };
-
-                        let payload = json!(contacts_message);
-
-                        success_resp.push(payload);
-                    } else if r#type == "reaction" {
-                        let reaction_message = Reaction {
-                            from: message
-                                .get("from")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            id: message
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            timestamp: message
-                                .get("timestamp")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            r#type: message
-                                .get("type")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_else(|| "reaction")
-                                .to_string(),
-
-                            reaction: {
-                                let react =
-                                    message.get("reaction").unwrap_or(&serde_json::Value::Null);
-                                ReactionPayload {
-                                    emoji: react
-                                        .get("emoji")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    message_id: react
-                                        .get("message_id")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                }
-                            },
-                        };
-
-                        let payload = json!(reaction_message);
-
-                        success_resp.push(payload);
-                    } else if r#type == "button" {
-                        let button_message = Button {
-                            from: message
-                                .get("from")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            id: message
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            timestamp: message
-                                .get("timestamp")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            r#type: message
-                                .get("type")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            context: {
-                                let context =
-                                    message.get("context").unwrap_or(&serde_json::Value::Null);
-                                MsgBtnContext {
-                                    from: context
-                                        .get("from")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    id: context
-                                        .get("id")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                }
-                            },
-
-                            button: {
-                                let button_details =
-                                    message.get("button").unwrap_or(&serde_json::Value::Null);
-                                ButtonDetails {
-                                    text: button_details
-                                        .get("text")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    payload: button_details
-                                        .get("payload")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                }
-                            },
-                        };
-
-                        let payload = json!(button_message);
-
-                        success_resp.push(payload);
-                    } else if r#type == "sticker" {
-                        let sticker_message = Sticker {
-                            from: message
-                                .get("from")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            id: message
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            timestamp: message
-                                .get("timestamp")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            r#type: message
-                                .get("type")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_else(|| "sticker")
-                                .to_string(),
-
-                            sticker: {
-                                let sticker =
-                                    message.get("sticker").unwrap_or(&serde_json::Value::Null);
-                                StickerData {
-                                    id: sticker
-                                        .get("id")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    animated: sticker
-                                        .get("animated")
-                                        .and_then(|v| v.as_bool())
-                                        .unwrap_or(false),
-
-                                    mime_type: sticker
-                                        .get("mime_type")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    sha256: sticker
-                                        .get("sha256")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                }
-                            },
-                        };
-
-                        let payload = json!(sticker_message);
-
-                        success_resp.push(payload);
-                    } else if r#type == "video" {
-                        let video_message = Video {
-                            from: message
-                                .get("from")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            id: message
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            timestamp: message
-                                .get("timestamp")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            r#type: message
-                                .get("type")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_else(|| "video")
-                                .to_string(),
-
-                            document: {
-                                let document =
-                                    message.get("document").unwrap_or(&serde_json::Value::Null);
-                                VideoData {
-                                    caption: document
-                                        .get("caption")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    mime_type: document
-                                        .get("mime_type")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    sha256: document
-                                        .get("sha256")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    id: document
-                                        .get("id")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                }
-                            },
-                        };
-
-                        let payload = json!(video_message);
-
-                        success_resp.push(payload);
-                    } else if r#type == "audio" {
-                        let audio_message = Audio {
-                            from: message
-                                .get("from")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            id: message
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            timestamp: message
-                                .get("timestamp")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            r#type: message
-                                .get("type")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_else(|| "audio")
-                                .to_string(),
-
-                            document: {
-                                let document =
-                                    message.get("document").unwrap_or(&serde_json::Value::Null);
-                                AudioData {
-                                    caption: document
-                                        .get("caption")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    mime_type: document
-                                        .get("mime_type")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-
-                                    sha256: document
-                                        .get("sha256")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::Contacts(contacts_message))
+    } else if r#type == "reaction" {
+        let reaction_message = Reaction {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("reaction")
+                .to_string(),
+
+            reaction: {
+                let react = message.get("reaction").unwrap_or(&serde_json::Value::Null);
+                ReactionPayload {
+                    emoji: react
+                        .get("emoji")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    message_id: react
+                        .get("message_id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                }
+            },
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::Reaction(reaction_message))
+    } else if r#type == "button" {
+        let button_message = Button {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            button: {
+                let button_details = message.get("button").unwrap_or(&serde_json::Value::Null);
+                ButtonDetails {
+                    text: button_details
+                        .get("text")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    payload: button_details
+                        .get("payload")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                }
+            },
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::Button(button_message))
+    } else if r#type == "interactive" {
+        let interactive = message
+            .get("interactive")
+            .unwrap_or(&serde_json::Value::Null);
+        let reply_type = interactive
+            .get("type")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+
+        let reply = if reply_type == "button_reply" {
+            let button_reply = interactive
+                .get("button_reply")
+                .unwrap_or(&serde_json::Value::Null);
+            InteractiveReply::ButtonReply {
+                button_reply: ButtonReply {
+                    id: button_reply
+                        .get("id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    title: button_reply
+                        .get("title")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                },
+            }
+        } else if reply_type == "list_reply" {
+            let list_reply = interactive
+                .get("list_reply")
+                .unwrap_or(&serde_json::Value::Null);
+            InteractiveReply::ListReply {
+                list_reply: ListReply {
+                    id: list_reply
+                        .get("id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    title: list_reply
+                        .get("title")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    description: list_reply
+                        .get("description")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                },
+            }
+        } else if reply_type == "nfm_reply" {
+            let nfm_reply = interactive
+                .get("nfm_reply")
+                .unwrap_or(&serde_json::Value::Null);
+            InteractiveReply::NfmReply {
+                nfm_reply: FlowReply {
+                    name: nfm_reply
+                        .get("name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    body: nfm_reply
+                        .get("body")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    response_json: nfm_reply
+                        .get("response_json")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                },
+            }
+        } else {
+            return Ok(IncomingMessage::Other(parse_other(message, value)));
+        };
+
+        let interactive_message = Interactive {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("interactive")
+                .to_string(),
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            interactive: reply,
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::Interactive(interactive_message))
+    } else if r#type == "system" {
+        let system_message = System {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("system")
+                .to_string(),
+
+            system: {
+                let system = message.get("system").unwrap_or(&serde_json::Value::Null);
+                SystemPayload {
+                    body: system
+                        .get("body")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    r#type: system
+                        .get("type")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    wa_id: system
+                        .get("wa_id")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+
+                    new_wa_id: system
+                        .get("new_wa_id")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+
+                    identity: system
+                        .get("identity")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+
+                    customer: system
+                        .get("customer")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+                }
+            },
+
+            identity: message.get("identity").map(|identity| Identity {
+                acknowledged: identity
+                    .get("acknowledged")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+
+                created_timestamp: identity
+                    .get("created_timestamp")
+                    .map(Timestamp::from_value)
+                    .unwrap_or_default(),
+
+                hash: identity
+                    .get("hash")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            }),
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::System(system_message))
+    } else if r#type == "sticker" {
+        let sticker_message = Sticker {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("sticker")
+                .to_string(),
+
+            sticker: {
+                let sticker = message.get("sticker").unwrap_or(&serde_json::Value::Null);
+                StickerData {
+                    id: sticker
+                        .get("id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    animated: sticker
+                        .get("animated")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false),
+
+                    mime_type: sticker
+                        .get("mime_type")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    sha256: sticker
+                        .get("sha256")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                }
+            },
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::Sticker(sticker_message))
+    } else if r#type == "video" {
+        let video_message = Video {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("video")
+                .to_string(),
+
+            video: {
+                let video = message.get("video").unwrap_or(&serde_json::Value::Null);
+                VideoData {
+                    caption: video
+                        .get("caption")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+
+                    mime_type: video
+                        .get("mime_type")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    sha256: video
+                        .get("sha256")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    id: video
+                        .get("id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                }
+            },
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::Video(video_message))
+    } else if r#type == "audio" {
+        let audio_message = Audio {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("audio")
+                .to_string(),
+
+            audio: {
+                let audio = message.get("audio").unwrap_or(&serde_json::Value::Null);
+                AudioData {
+                    mime_type: audio
+                        .get("mime_type")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    sha256: audio
+                        .get("sha256")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    id: audio
+                        .get("id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    voice: audio
+                        .get("voice")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false),
+                }
+            },
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::Audio(audio_message))
+    } else if r#type == "document" {
+        let document_message = Document {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("document")
+                .to_string(),
+
+            document: {
+                let document = message.get("document").unwrap_or(&serde_json::Value::Null);
+                DocumentData {
+                    caption: document
+                        .get("caption")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+
+                    filename: document
+                        .get("filename")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+
+                    mime_type: document
+                        .get("mime_type")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    sha256: document
+                        .get("sha256")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    id: document
+                        .get("id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                }
+            },
+
+            context: parse_context(message),
+
+            sender: parse_sender(message, &value.contacts),
+
+            metadata: Some(value.metadata.clone()),
+
+            referral: parse_referral(message),
+        };
+
+        Ok(IncomingMessage::Document(document_message))
+    } else if r#type == "image" {
+        let image_message = Image {
+            from: message
+                .get("from")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            id: message
+                .get("id")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+
+            timestamp: message
+                .get("timestamp")
+                .map(Timestamp::from_value)
+                .unwrap_or_default(),
+
+            r#type: message
+                .get("type")
+                .and_then(|v| v.as_str())
+                .unwrap_or("image")
+                .to_string(),
+
+            image: {
+                let image = message.get("image").unwrap_or(&serde_json::Value::Null);
+                ImageData {
+                    caption: image
+                        .get("caption")
+                        .and_then(|v| v.as_str())
+                        .map(|v| v.to_string()),
+
+                    mime_type: image
+                        .get("mime_type")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    sha256: image
+                        .get("sha256")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+
+                    id: image
+                        .get("id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                }
+            },
 
-                                    id: document
-                                        .get("id")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                }
-                            },
-                        };
+            context: parse_context(message),
 
-                        let payload = json!(audio_message);
+            sender: parse_sender(message, &value.contacts),
 
-                        success_resp.push(payload);
-                    } else if r#type == "document" {
-                        let document_message = Document {
-                            from: message
-                                .get("from")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
+            metadata: Some(value.metadata.clone()),
 
-                            id: message
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
+            referral: parse_referral(message),
+        };
 
-                            timestamp: message
-                                .get("timestamp")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
+        Ok(IncomingMessage::Image(image_message))
+    } else {
+        Ok(IncomingMessage::Other(parse_other(message, value)))
+    }
+}
 
-                            r#type: message
-                                .get("type")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_else(|| "document")
-                                .to_string(),
+/// Keeps a message of a type this crate does not model, with its raw JSON.
+fn parse_other(message: &Value, value: &Vaalue) -> Other {
+    Other {
+        from: message
+            .get("from")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
 
-                            document: {
-                                let document =
-                                    message.get("document").unwrap_or(&serde_json::Value::Null);
-                                DocumentData {
-                                    caption: document
-                                        .get("caption")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
+        id: message
+            .get("id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
 
-                                    mime_type: document
-                                        .get("mime_type")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
+        timestamp: message
+            .get("timestamp")
+            .map(Timestamp::from_value)
+            .unwrap_or_default(),
 
-                                    sha256: document
-                                        .get("sha256")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
+        r#type: message
+            .get("type")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
 
-                                    id: document
-                                        .get("id")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                }
-                            },
-                        };
+        raw: message.clone(),
 
-                        let payload = json!(document_message);
+        context: parse_context(message),
 
-                        success_resp.push(payload);
-                    } else if r#type == "image" {
-                        let image_message = Image {
-                            from: message
-                                .get("from")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
+        sender: parse_sender(message, &value.contacts),
 
-                            id: message
-                                .get("id")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
-
-                            timestamp: message
-                                .get("timestamp")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_default()
-                                .to_string(),
+        metadata: Some(value.metadata.clone()),
 
-                            r#type: message
-                                .get("type")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or_else(|| "image")
-                                .to_string(),
-
-                            image: {
-                                let image =
-                                    message.get("image").unwrap_or(&serde_json::Value::Null);
-                                ImageData {
-                                    caption: image
-                                        .get("caption")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
+        referral: parse_referral(message),
+    }
+}
 
-                                    mime_type: image
-                                        .get("mime_type")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
+/// Reads a coordinate sent either as a JSON number or as a numeric string.
+fn parse_coordinate(value: &Value) -> Option<f64> {
+    value
+        .as_f64()
+        .or_else(|| value.as_str().and_then(|v| v.trim().parse().ok()))
+        .filter(|v: &f64| v.is_finite())
+}
 
-                                    sha256: image
-                                        .get("sha256")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
+/// Finds the profile of the message's sender in the change's `contacts`.
+///
+/// Falls back to the only contact of the change when no `wa_id` matches `from`, since
+/// WhatsApp may format the two numbers differently (e.g. Brazilian and Mexican numbers).
+fn parse_sender(
+    message: &Value,
+    contacts: &[full_messages_payload::Contact],
+) -> Option<full_messages_payload::Contact> {
+    let from = message
+        .get("from")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+    contacts
+        .iter()
+        .find(|contact| contact.wa_id == from)
+        .or(match contacts {
+            [contact] => Some(contact),
+            _ => None,
+        })
+        .cloned()
+}
 
-                                    id: image
-                                        .get("id")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or_default()
-                                        .to_string(),
-                                }
-                            },
-                        };
+/// Extracts the `context` block shared by every incoming message type.
+fn parse_context(message: &Value) -> Option<Context> {
+    let context = message.get("context")?;
+    Some(Context {
+        from: context
+            .get("from")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string()),
+
+        id: context
+            .get("id")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string()),
+
+        forwarded: context
+            .get("forwarded")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+
+        frequently_forwarded: context
+            .get("frequently_forwarded")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+
+        referred_product: context
+            .get("referred_product")
+            .map(|product| ReferredProduct {
+                catalog_id: product
+                    .get("catalog_id")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+
+                product_retailer_id: product
+                    .get("product_retailer_id")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            }),
+    })
+}
 
-                        let payload = json!(image_message);
+/// Extracts the `referral` block shared by every incoming message type.
+fn parse_referral(message: &Value) -> Option<Referral> {
+    let referral = message.get("referral")?;
+    Some(Referral {
+        source_url: referral
+            .get("source_url")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+
+        source_id: referral
+            .get("source_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+
+        source_type: referral
+            .get("source_type")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+
+        headline: referral
+            .get("headline")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+
+        body: referral
+            .get("body")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+
+        media_type: referral
+            .get("media_type")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+
+        image_url: referral
+            .get("image_url")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string()),
+
+        video_url: referral
+            .get("video_url")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string()),
+
+        thumbnail_url: referral
+            .get("thumbnail_url")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string()),
+
+        ctwa_clid: referral
+            .get("ctwa_clid")
+            .and_then(|v| v.as_str())
+            .map(|v| v.to_string()),
+
+        welcome_message: referral
+            .get("welcome_message")
+            .map(|welcome_message| WelcomeMessage {
+                text: welcome_message
+                    .get("text")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            }),
+    })
+}
 
-                        success_resp.push(payload);
-                    } else {
-                        error_resp.push("Message format could not be recognized".to_string());
-                        continue;
-                    }
-                }
-            }
-        }
+/// Converts an entry of `value.errors` into an error detail.
+fn parse_error_detail(error: &Value) -> ErrorDetail {
+    ErrorDetail {
+        code: error.get("code").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
+
+        details: error
+            .get("error_data")
+            .and_then(|data| data.get("details"))
+            .or_else(|| error.get("details"))
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
+
+        title: error
+            .get("title")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string(),
     }
-
-    Ok((success_resp, error_resp))
-
-    ////////////////////////////////////
-
-    // let message: Value = serde_json::from_str(message).map_err(|e| e.to_string())?;
-
-    // let entries = message
-    //     .get("entry")
-    //     .map(|value| value.as_array())
-    //     .unwrap_or_default();
-
-    // if entries.is_none() {
-    //     return Err("Entry data not found".to_string());
-    // }
-
-    // for (index, entry) in entries.unwrap().iter().enumerate() {
-    //     let entry_id = entry
-    //         .get("id")
-    //         .map(|value| value.to_string())
-    //         .unwrap_or_default();
-
-    //     if entry_id.trim().trim_matches('"') != config.whatsapp_business_id.trim() {
-    //         return Err(format!(
-    //             "Whatsapp_business_id does not tally with config whatsapp_business_id: {}",
-    //             index
-    //         )
-    //         .to_string());
-    //     }
-
-    //     if let Some(changes) = entry.get("changes").and_then(|c| c.as_array()) {
-    //         for change in changes {
-    //             let value = change.get("value").and_then(|v| v.as_object());
-
-    //             if value.is_none() {
-    //                 return Err(format!("Value not found: {} index: ", index).to_string());
-    //             }
-
-    //             let phone_number_id = value
-    //                 .and_then(|v| v.get("metadata"))
-    //                 .and_then(|m| m.get("phone_number_id"))
-    //                 .and_then(|id| id.as_str())
-    //                 .unwrap_or_default();
-
-    //             if phone_number_id.trim().trim_matches('"')
-    //                 != config.whatsapp_phone_number_id.trim()
-    //             {
-    //                 return Err(format!("Message whatsapp_phone_number_id does not tally with config whatsapp_phone_number_id").to_string());
-    //             }
-
-    //             if let Some(messages) = value
-    //                 .and_then(|v| v.get("messages"))
-    //                 .and_then(|m| m.as_array())
-    //             {
-    //                 for message in messages {
-    //                     // identify message type
-
-    //                     if let Some(r#type) = message.get("type") {
-    //                         if r#type == "order" {
-    //                             let order = Order {
-    //                                 from: message
-    //                                     .get("from")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 id: message
-    //                                     .get("id")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 context: {
-    //                                     let context = message
-    //                                         .get("context")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     MessageContext {
-    //                                         from: context
-    //                                             .get("from")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         id: context
-    //                                             .get("id")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-    //                                     }
-    //                                 },
-
-    //                                 r#type: message
-    //                                     .get("type")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 timestamp: message
-    //                                     .get("timestamp")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 order: {
-    //                                     let order_details = message
-    //                                         .get("order")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     OrderDetails {
-    //                                         catalog_id: order_details
-    //                                             .get("catalog_id")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         product_items: {
-    //                                             let product_items = order_details
-    //                                                 .get("product_items")
-    //                                                 .unwrap_or(&serde_json::Value::Null);
-    //                                             product_items
-    //                                                 .as_array()
-    //                                                 .unwrap_or(&vec![])
-    //                                                 .iter()
-    //                                                 .map(|item| ProductItem {
-    //                                                     product_retailer_id: item
-    //                                                         .get("product_retailer_id")
-    //                                                         .and_then(|v| v.as_str())
-    //                                                         .unwrap_or_default()
-    //                                                         .to_string(),
-
-    //                                                     quantity: item
-    //                                                         .get("quantity")
-    //                                                         .and_then(|v| v.as_str())
-    //                                                         .unwrap_or_default()
-    //                                                         .to_string(),
-
-    //                                                     item_price: item
-    //                                                         .get("item_price")
-    //                                                         .and_then(|v| v.as_str())
-    //                                                         .unwrap_or_default()
-    //                                                         .to_string(),
-
-    //                                                     currency: item
-    //                                                         .get("currency")
-    //                                                         .and_then(|v| v.as_str())
-    //                                                         .unwrap_or_default()
-    //                                                         .to_string(),
-    //                                                 })
-    //                                                 .collect::<Vec<_>>()
-    //                                         },
-
-    //                                         text: order_details
-    //                                             .get("text")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-    //                                     }
-    //                                 },
-    //                             };
-
-    //                             let payload = json!(order);
-
-    //                             message_payload.push(payload)
-    //                         } else if r#type == "text" {
-    //                             let text_message = Text {
-    //                                 from: message
-    //                                     .get("from")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 id: message
-    //                                     .get("id")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 timestamp: message
-    //                                     .get("timestamp")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 r#type: message
-    //                                     .get("type")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 text: TextPayload {
-    //                                     body: message
-    //                                         .get("text")
-    //                                         .and_then(|text_obj| text_obj.get("body"))
-    //                                         .and_then(|v| v.as_str())
-    //                                         .unwrap_or_default()
-    //                                         .to_string(),
-    //                                 },
-    //                             };
-
-    //                             let payload = json!(text_message);
-
-    //                             message_payload.push(payload);
-    //                         } else if r#type == "unknown" {
-    //                             let unknown_message = Unknown {
-    //                                 from: message
-    //                                     .get("from")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 id: message
-    //                                     .get("id")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 timestamp: message
-    //                                     .get("timestamp")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 r#type: message
-    //                                     .get("type")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 errors: message
-    //                                     .get("errors")
-    //                                     .and_then(|v| v.as_array())
-    //                                     .map(|arr| {
-    //                                         arr.iter()
-    //                                             .map(|err| {
-    //                                                 ErrorDetail {
-    //                                                     code: err
-    //                                                         .get("code")
-    //                                                         .and_then(|v| v.as_u64())
-    //                                                         .unwrap_or(0)
-    //                                                         as u32, // assuming `code` is u32
-
-    //                                                     details: err
-    //                                                         .get("details")
-    //                                                         .and_then(|v| v.as_str())
-    //                                                         .unwrap_or_default()
-    //                                                         .to_string(),
-
-    //                                                     title: err
-    //                                                         .get("title")
-    //                                                         .and_then(|v| v.as_str())
-    //                                                         .unwrap_or_default()
-    //                                                         .to_string(),
-    //                                                 }
-    //                                             })
-    //                                             .collect()
-    //                                     })
-    //                                     .unwrap_or_else(Vec::new),
-    //                             };
-
-    //                             let payload = json!(unknown_message);
-
-    //                             message_payload.push(payload);
-    //                         } else if r#type == "location" {
-    //                             let location_message = Location {
-    //                                 from: message
-    //                                     .get("from")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 id: message
-    //                                     .get("id")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 timestamp: message
-    //                                     .get("timestamp")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 location: {
-    //                                     let loc = message
-    //                                         .get("location")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     LocationPayload {
-    //                                         latitude: loc
-    //                                             .get("latitude")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         longitude: loc
-    //                                             .get("longitude")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         name: loc
-    //                                             .get("name")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         address: loc
-    //                                             .get("address")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-    //                                     }
-    //                                 },
-    //                             };
-
-    //                             let payload = json!(location_message);
-
-    //                             message_payload.push(payload);
-    //                         } else if r#type == "contacts" {
-    //                             let contacts_message = Contact {
-    //                                 addresses: {
-    //                                     let addresses = message
-    //                                         .get("addresses")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     addresses
-    //                                         .as_array()
-    //                                         .unwrap_or(&vec![])
-    //                                         .iter()
-    //                                         .map(|addr| Address {
-    //                                             city: addr
-    //                                                 .get("city")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-
-    //                                             country: addr
-    //                                                 .get("country")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-
-    //                                             country_code: addr
-    //                                                 .get("country_code")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-
-    //                                             state: addr
-    //                                                 .get("state")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-
-    //                                             street: addr
-    //                                                 .get("street")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-
-    //                                             r#type: addr
-    //                                                 .get("type")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-
-    //                                             zip: addr
-    //                                                 .get("zip")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-    //                                         })
-    //                                         .collect::<Vec<_>>()
-    //                                 },
-
-    //                                 birthday: message
-    //                                     .get("birthday")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 emails: {
-    //                                     let emails = message
-    //                                         .get("emails")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     emails
-    //                                         .as_array()
-    //                                         .unwrap_or(&vec![])
-    //                                         .iter()
-    //                                         .map(|email| Email {
-    //                                             email: email
-    //                                                 .get("email")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-
-    //                                             r#type: email
-    //                                                 .get("type")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-    //                                         })
-    //                                         .collect::<Vec<_>>()
-    //                                 },
-
-    //                                 name: {
-    //                                     let name =
-    //                                         message.get("name").unwrap_or(&serde_json::Value::Null);
-    //                                     Name {
-    //                                         formatted_name: name
-    //                                             .get("formatted_name")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         first_name: name
-    //                                             .get("first_name")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         last_name: name
-    //                                             .get("last_name")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         middle_name: name
-    //                                             .get("middle_name")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         suffix: name
-    //                                             .get("suffix")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         prefix: name
-    //                                             .get("prefix")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-    //                                     }
-    //                                 },
-
-    //                                 org: {
-    //                                     let org =
-    //                                         message.get("org").unwrap_or(&serde_json::Value::Null);
-    //                                     Organization {
-    //                                         company: org
-    //                                             .get("company")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         department: org
-    //                                             .get("department")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         title: org
-    //                                             .get("title")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-    //                                     }
-    //                                 },
-
-    //                                 phones: {
-    //                                     let phones = message
-    //                                         .get("phones")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     phones
-    //                                         .as_array()
-    //                                         .unwrap_or(&vec![])
-    //                                         .iter()
-    //                                         .map(|phone| Phone {
-    //                                             phone: phone
-    //                                                 .get("phone")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-
-    //                                             wa_id: phone
-    //                                                 .get("wa_id")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-
-    //                                             r#type: phone
-    //                                                 .get("type")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-    //                                         })
-    //                                         .collect::<Vec<_>>()
-    //                                 },
-
-    //                                 urls: {
-    //                                     let urls =
-    //                                         message.get("urls").unwrap_or(&serde_json::Value::Null);
-    //                                     urls.as_array()
-    //                                         .unwrap_or(&vec![])
-    //                                         .iter()
-    //                                         .map(|url| Url {
-    //                                             url: url
-    //                                                 .get("url")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-
-    //                                             r#type: url
-    //                                                 .get("type")
-    //                                                 .and_then(|v| v.as_str())
-    //                                                 .unwrap_or_default()
-    //                                                 .to_string(),
-    //                                         })
-    //                                         .collect::<Vec<_>>()
-    //                                 },
-    //                             };
-
-    //                             let payload = json!(contacts_message);
-
-    //                             message_payload.push(payload);
-    //                         } else if r#type == "reaction" {
-    //                             let reaction_message = Reaction {
-    //                                 from: message
-    //                                     .get("from")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 id: message
-    //                                     .get("id")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 timestamp: message
-    //                                     .get("timestamp")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 r#type: message
-    //                                     .get("type")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_else(|| "reaction")
-    //                                     .to_string(),
-
-    //                                 reaction: {
-    //                                     let react = message
-    //                                         .get("reaction")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     ReactionPayload {
-    //                                         emoji: react
-    //                                             .get("emoji")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         message_id: react
-    //                                             .get("message_id")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-    //                                     }
-    //                                 },
-    //                             };
-
-    //                             let payload = json!(reaction_message);
-
-    //                             message_payload.push(payload);
-    //                         } else if r#type == "button" {
-    //                             let button_message = Button {
-    //                                 from: message
-    //                                     .get("from")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 id: message
-    //                                     .get("id")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 timestamp: message
-    //                                     .get("timestamp")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 r#type: message
-    //                                     .get("type")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 context: {
-    //                                     let context = message
-    //                                         .get("context")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     MsgBtnContext {
-    //                                         from: context
-    //                                             .get("from")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         id: context
-    //                                             .get("id")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-    //                                     }
-    //                                 },
-
-    //                                 button: {
-    //                                     let button_details = message
-    //                                         .get("button")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     ButtonDetails {
-    //                                         text: button_details
-    //                                             .get("text")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         payload: button_details
-    //                                             .get("payload")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-    //                                     }
-    //                                 },
-    //                             };
-
-    //                             let payload = json!(button_message);
-
-    //                             message_payload.push(payload);
-    //                         } else if r#type == "sticker" {
-    //                             let sticker_message = Sticker {
-    //                                 from: message
-    //                                     .get("from")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 id: message
-    //                                     .get("id")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 timestamp: message
-    //                                     .get("timestamp")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 r#type: message
-    //                                     .get("type")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_else(|| "sticker")
-    //                                     .to_string(),
-
-    //                                 sticker: {
-    //                                     let sticker = message
-    //                                         .get("sticker")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     StickerData {
-    //                                         id: sticker
-    //                                             .get("id")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         animated: sticker
-    //                                             .get("animated")
-    //                                             .and_then(|v| v.as_bool())
-    //                                             .unwrap_or(false),
-
-    //                                         mime_type: sticker
-    //                                             .get("mime_type")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         sha256: sticker
-    //                                             .get("sha256")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-    //                                     }
-    //                                 },
-    //                             };
-
-    //                             let payload = json!(sticker_message);
-
-    //                             message_payload.push(payload);
-    //                         } else if r#type == "video" {
-    //                             let video_message = Video {
-    //                                 from: message
-    //                                     .get("from")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 id: message
-    //                                     .get("id")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 timestamp: message
-    //                                     .get("timestamp")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 r#type: message
-    //                                     .get("type")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_else(|| "video")
-    //                                     .to_string(),
-
-    //                                 document: {
-    //                                     let document = message
-    //                                         .get("document")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     VideoData {
-    //                                         caption: document
-    //                                             .get("caption")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         mime_type: document
-    //                                             .get("mime_type")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         sha256: document
-    //                                             .get("sha256")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         id: document
-    //                                             .get("id")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-    //                                     }
-    //                                 },
-    //                             };
-
-    //                             let payload = json!(video_message);
-
-    //                             message_payload.push(payload);
-    //                         } else if r#type == "audio" {
-    //                             let audio_message = Audio {
-    //                                 from: message
-    //                                     .get("from")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 id: message
-    //                                     .get("id")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 timestamp: message
-    //                                     .get("timestamp")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 r#type: message
-    //                                     .get("type")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_else(|| "audio")
-    //                                     .to_string(),
-
-    //                                 document: {
-    //                                     let document = message
-    //                                         .get("document")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     AudioData {
-    //                                         caption: document
-    //                                             .get("caption")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         mime_type: document
-    //                                             .get("mime_type")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         sha256: document
-    //                                             .get("sha256")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         id: document
-    //                                             .get("id")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-    //                                     }
-    //                                 },
-    //                             };
-
-    //                             let payload = json!(audio_message);
-
-    //                             message_payload.push(payload);
-    //                         } else if r#type == "document" {
-    //                             let document_message = Document {
-    //                                 from: message
-    //                                     .get("from")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 id: message
-    //                                     .get("id")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 timestamp: message
-    //                                     .get("timestamp")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 r#type: message
-    //                                     .get("type")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_else(|| "document")
-    //                                     .to_string(),
-
-    //                                 document: {
-    //                                     let document = message
-    //                                         .get("document")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     DocumentData {
-    //                                         caption: document
-    //                                             .get("caption")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         mime_type: document
-    //                                             .get("mime_type")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         sha256: document
-    //                                             .get("sha256")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         id: document
-    //                                             .get("id")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-    //                                     }
-    //                                 },
-    //                             };
-
-    //                             let payload = json!(document_message);
-
-    //                             message_payload.push(payload);
-    //                         } else if r#type == "image" {
-    //                             let image_message = Image {
-    //                                 from: message
-    //                                     .get("from")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 id: message
-    //                                     .get("id")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 timestamp: message
-    //                                     .get("timestamp")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_default()
-    //                                     .to_string(),
-
-    //                                 r#type: message
-    //                                     .get("type")
-    //                                     .and_then(|v| v.as_str())
-    //                                     .unwrap_or_else(|| "image")
-    //                                     .to_string(),
-
-    //                                 image: {
-    //                                     let image = message
-    //                                         .get("image")
-    //                                         .unwrap_or(&serde_json::Value::Null);
-    //                                     ImageData {
-    //                                         caption: image
-    //                                             .get("caption")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         mime_type: image
-    //                                             .get("mime_type")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         sha256: image
-    //                                             .get("sha256")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-
-    //                                         id: image
-    //                                             .get("id")
-    //                                             .and_then(|v| v.as_str())
-    //                                             .unwrap_or_default()
-    //                                             .to_string(),
-    //                                     }
-    //                                 },
-    //                             };
-
-    //                             let payload = json!(image_message);
-
-    //                             message_payload.push(payload);
-    //                         } else {
-    //                             return Err(format!("Message type not understood {:?}", message));
-    //                         }
-    //                     }
-    //                 }
-    //             }
-    //         }
-    //     }
-    // }
-
-    // Ok(message_payload)
 }
 
-pub fn find_statuses(config: Config, message: &str) -> Result<(Vec<Value>, Vec<String>), String> {
-    let mut error_resp: Vec<String> = Vec::new();
-    let mut success_resp: Vec<Value> = Vec::new();
-
-    let payload: WebhookStatusesPayload = serde_json::from_str(message).unwrap();
-
-    for (entry_index, entry) in payload.entry.iter().enumerate() {
-        if entry.id.trim().trim_matches('"') != config.whatsapp_business_id {
-            error_resp.push(format!("Entry id not recognized index: {}", entry_index));
-            continue;
+/// Deserializes a webhook envelope without panicking.
+///
+/// Syntax errors are reported with their line and column; shape mismatches are
+/// reported with the JSON path and the entry/change indices they occurred in.
+fn parse_payload<T: DeserializeOwned>(payload: &str) -> Result<T, IncomingError> {
+    let deserializer = &mut serde_json::Deserializer::from_str(payload);
+    serde_path_to_error::deserialize(deserializer).map_err(|error| {
+        let path = error.path().clone();
+        let inner = error.into_inner();
+        if inner.is_syntax() || inner.is_eof() {
+            return IncomingError::Syntax {
+                line: inner.line(),
+                column: inner.column(),
+                message: inner.to_string(),
+            };
         }
-        for (change_index, change) in entry.changes.iter().enumerate() {
-            if change
-                .value
-                .metadata
-                .phone_number_id
-                .trim()
-                .trim_matches('"')
-                != config.whatsapp_phone_number_id
-            {
-                error_resp.push(format!(
-                    "Metadata phone_number_id not recognized index: {}",
-                    change_index
-                ));
-                continue;
-            }
-            for status in change.value.statuses.iter() {
-                success_resp.push(json!(status));
+
+        let mut entry_index = None;
+        let mut change_index = None;
+        let mut key = "";
+        for segment in path.iter() {
+            match segment {
+                Segment::Map { key: name } => key = name,
+                Segment::Seq { index } if key == "entry" => entry_index = Some(*index),
+                Segment::Seq { index } if key == "changes" => change_index = Some(*index),
+                _ => {}
             }
         }
-    }
 
-    Ok((success_resp, error_resp))
+        IncomingError::Payload {
+            path: path.to_string(),
+            entry_index,
+            change_index,
+            message: inner.to_string(),
+        }
+    })
 }
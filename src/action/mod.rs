@@ -1,2 +1,3 @@
 pub mod incoming;
-pub mod outgoing;
\ No newline at end of file
+pub mod outgoing;
+pub mod webhook;
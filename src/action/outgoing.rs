@@ -1,33 +1,90 @@
 use crate::config::Config;
-use crate::formatter::outgoing_type::MessageType;
-use reqwest::{Client, header};
+use crate::error::{GraphApiError, SendError};
+use crate::formatter::outgoing_type::{MessageType, response::SendResponse};
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
 use serde_json::Value;
+use std::time::Duration;
 
 /// Sends a WhatsApp message using the configured API details.
 ///
+/// Each call opens its own HTTP connection. To reuse connections across sends, or to
+/// configure timeouts, a proxy or the user agent, use `client::WhatsAppClient`.
+///
 /// # Arguments
 ///
-/// * `config` - Reference to the `Config` struct containing WhatsApp API details 
-///              like base URL, version, phone number ID, and auth token.
+/// * `config` - Reference to the `Config` struct containing WhatsApp API details
+///   like base URL, version, phone number ID, and auth token.
 /// * `message` - A reference to the `MessageType` enum, which wraps the outgoing message payload.
 ///
 /// # Returns
 ///
 /// A `Result` with:
-/// - `Ok(Value)` → the parsed JSON response from the WhatsApp API if the request was successful.
-/// - `Err(reqwest::Error)` → an error if the HTTP request or response parsing failed.
+/// - `Ok(SendResponse)` → the id of the sent message and the recipient's WhatsApp ID.
+/// - `Err(SendError)` → the request failed, or the API answered with an error.
 ///
 /// # Example
 ///
 /// ```ignore
 /// let response = send(&config, &message).await?;
-/// println!("WhatsApp response: {:?}", response);
+/// println!("Sent message: {:?}", response.message_id());
 /// ```
-pub async fn send(config: &Config, message: &MessageType) -> Result<Value, reqwest::Error> {
-    let client = Client::new();
+pub async fn send(config: &Config, message: &MessageType) -> Result<SendResponse, SendError> {
+    send_with(&Client::new(), config, message).await
+}
 
-    println!("message ====> {:?}", &message);
+/// Sends a WhatsApp message through the given HTTP client.
+///
+/// Every attempt first waits for `config.rate_limiter`, if set. Retryable failures are
+/// sent again according to `config.retry_policy`.
+///
+/// # Arguments
+///
+/// * `client` - The HTTP client whose connection pool is used.
+/// * `config` - WhatsApp API details.
+/// * `message` - The outgoing message payload.
+///
+/// # Returns
+///
+/// The same as `send`. When every attempt fails, or the API asks to wait longer than the
+/// policy allows, the error of the last attempt is returned.
+pub async fn send_with(
+    client: &Client,
+    config: &Config,
+    message: &MessageType,
+) -> Result<SendResponse, SendError> {
+    let policy = &config.retry_policy;
+    let mut attempt = 1;
+    loop {
+        if let Some(rate_limiter) = &config.rate_limiter {
+            rate_limiter
+                .acquire(&config.whatsapp_phone_number_id, message.recipient())
+                .await;
+        }
+        let result = send_once(client, config, message).await;
+        let delay = match &result {
+            Err(error) if error.is_retryable() && attempt < policy.max_attempts() => {
+                policy.delay(attempt, error.retry_after())
+            }
+            _ => None,
+        };
+        match delay {
+            Some(delay) => {
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            None => return result,
+        }
+    }
+}
 
+/// Sends a WhatsApp message a single time.
+async fn send_once(
+    client: &Client,
+    config: &Config,
+    message: &MessageType,
+) -> Result<SendResponse, SendError> {
     let resp = client
         .post(format!(
             "{}/{}/{}/messages",
@@ -40,9 +97,52 @@ pub async fn send(config: &Config, message: &MessageType) -> Result<Value, reqwe
         )
         .json(message)
         .send()
-        .await?
-        .json::<Value>()
         .await?;
 
-    Ok(resp)
+    let status = resp.status();
+    let headers = resp.headers().clone();
+    let body = resp.text().await?;
+    decode_response(status, &headers, &body)
+}
+
+/// Turns a `/messages` response into a `SendResponse` or a `SendError`.
+///
+/// A body carrying a Graph API `error` object is an error even when the status is `2xx`.
+pub(crate) fn decode_response(
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &str,
+) -> Result<SendResponse, SendError> {
+    let value: Option<Value> = serde_json::from_str(body).ok();
+    let retry_after = headers
+        .get(header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .map(Duration::from_secs);
+
+    let api_error = value
+        .as_ref()
+        .and_then(|value| value.get("error"))
+        .and_then(|error| GraphApiError::deserialize(error).ok());
+    if let Some(error) = api_error {
+        return Err(SendError::Api {
+            status: status.as_u16(),
+            retry_after,
+            error: Box::new(error),
+        });
+    }
+
+    match value {
+        Some(value) if status.is_success() => {
+            SendResponse::deserialize(value).map_err(|error| SendError::Decode {
+                body: body.to_string(),
+                message: error.to_string(),
+            })
+        }
+        _ => Err(SendError::Http {
+            status: status.as_u16(),
+            retry_after,
+            body: body.to_string(),
+        }),
+    }
 }
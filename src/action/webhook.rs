@@ -0,0 +1,87 @@
+use crate::error::{SignatureError, SubscriptionError};
+use crate::formatter::incoming_type::subscription::SubscriptionQuery;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Prefix Meta puts in front of the hex digest in `X-Hub-Signature-256`.
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+/// The only `hub.mode` Meta sends when verifying a callback URL.
+const SUBSCRIBE_MODE: &str = "subscribe";
+
+/// Verifies a webhook payload against its `X-Hub-Signature-256` header.
+///
+/// # Arguments
+///
+/// * `app_secret` - The app secret shown in the Meta app dashboard.
+/// * `body` - The raw request body, exactly as received.
+/// * `signature` - The value of the `X-Hub-Signature-256` header, if present.
+///
+/// # Returns
+///
+/// A `Result` with:
+/// - `Ok(&str)` → the body as UTF-8, ready to be handed to the incoming parsers.
+/// - `Err(SignatureError)` → the header is missing, malformed, or does not match.
+///
+/// The digest comparison runs in constant time.
+pub fn verify_signature<'a>(
+    app_secret: &str,
+    body: &'a [u8],
+    signature: Option<&str>,
+) -> Result<&'a str, SignatureError> {
+    let signature = signature
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .ok_or(SignatureError::Missing)?;
+
+    let digest = signature
+        .strip_prefix(SIGNATURE_PREFIX)
+        .and_then(|hex_digest| hex::decode(hex_digest).ok())
+        .ok_or(SignatureError::Malformed)?;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(app_secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.verify_slice(&digest)
+        .map_err(|_| SignatureError::Mismatch)?;
+
+    std::str::from_utf8(body).map_err(|_| SignatureError::InvalidUtf8)
+}
+
+/// Answers the GET handshake Meta performs when a webhook callback URL is registered.
+///
+/// # Arguments
+///
+/// * `verify_token` - The verify token entered in the app dashboard.
+/// * `query` - The `hub.mode`, `hub.verify_token` and `hub.challenge` query parameters.
+///
+/// # Returns
+///
+/// A `Result` with:
+/// - `Ok(String)` → the `hub.challenge` value to echo back with `200 OK`.
+/// - `Err(SubscriptionError)` → the request should be rejected with `403 Forbidden`.
+pub fn verify_subscription(
+    verify_token: &str,
+    query: &SubscriptionQuery,
+) -> Result<String, SubscriptionError> {
+    let mode = query
+        .mode
+        .as_deref()
+        .ok_or(SubscriptionError::MissingParameter("hub.mode"))?;
+    if mode != SUBSCRIBE_MODE {
+        return Err(SubscriptionError::UnsupportedMode(mode.to_string()));
+    }
+
+    let token = query
+        .verify_token
+        .as_deref()
+        .ok_or(SubscriptionError::MissingParameter("hub.verify_token"))?;
+    if token != verify_token {
+        return Err(SubscriptionError::TokenMismatch);
+    }
+
+    query
+        .challenge
+        .clone()
+        .ok_or(SubscriptionError::MissingParameter("hub.challenge"))
+}
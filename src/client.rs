@@ -0,0 +1,163 @@
+use crate::action::outgoing;
+use crate::config::Config;
+use crate::error::SendError;
+use crate::formatter::outgoing_type::{MessageType, response::SendResponse};
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryPolicy;
+use reqwest::{Client, Proxy};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// User agent sent when none is configured.
+const DEFAULT_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// A long-lived client for the WhatsApp Cloud API.
+///
+/// The client owns one HTTP connection pool, so connections and TLS sessions are reused
+/// across sends. It is cheap to clone and can be shared between tasks; clones share the
+/// same pool and `Config`. Pooled connections are driven by the Tokio runtime that opened
+/// them, so use a client from a single long-lived runtime.
+///
+/// # Example
+/// ```ignore
+/// let client = WhatsAppClient::new(config)?;
+/// let response = client.send(&message).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct WhatsAppClient {
+    http: Client,
+    config: Arc<Config>,
+}
+
+impl WhatsAppClient {
+    /// Creates a client with the default HTTP settings.
+    ///
+    /// # Arguments
+    /// - `config`: API details used for every request.
+    ///
+    /// # Returns
+    /// The client, or a `reqwest::Error` if the HTTP client cannot be initialized.
+    pub fn new(config: Config) -> Result<Self, reqwest::Error> {
+        Self::builder(config).build()
+    }
+
+    /// Starts building a client with custom HTTP settings.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use whatsapp_handler::{client::WhatsAppClient, config::Config};
+    ///
+    /// let config = Config::from(
+    ///     "https://graph.facebook.com".to_string(),
+    ///     "v17.0".to_string(),
+    ///     "your-business-id".to_string(),
+    ///     "your-phone-id".to_string(),
+    ///     "your-token".to_string(),
+    /// );
+    ///
+    /// let client = WhatsAppClient::builder(config)
+    ///     .timeout(Duration::from_secs(10))
+    ///     .user_agent("my-bot/1.0")
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn builder(config: Config) -> WhatsAppClientBuilder {
+        WhatsAppClientBuilder {
+            config,
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
+    /// Returns the API details this client sends with.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Sends an outgoing WhatsApp message, retrying transient failures.
+    ///
+    /// # Arguments
+    /// - `message`: A structured `MessageType` representing the message to send.
+    ///
+    /// # Returns
+    /// A `Result` with:
+    /// - `Ok(SendResponse)`: The id of the sent message and the recipient's WhatsApp ID.
+    /// - `Err(SendError)`: If the request fails or the API answers with an error.
+    pub async fn send(&self, message: &MessageType) -> Result<SendResponse, SendError> {
+        outgoing::send_with(&self.http, &self.config, message).await
+    }
+}
+
+/// Builder for a `WhatsAppClient`, created with `WhatsAppClient::builder`.
+#[derive(Debug)]
+pub struct WhatsAppClientBuilder {
+    config: Config,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<Proxy>,
+    user_agent: String,
+}
+
+impl WhatsAppClientBuilder {
+    /// Sets the total timeout of each request, from connecting until the body is read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for establishing a connection.
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Routes every request through a proxy.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Sets how failed sends are retried, replacing `Config::retry_policy`.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.config.retry_policy = retry_policy;
+        self
+    }
+
+    /// Paces sends with a rate limiter, replacing `Config::rate_limiter`.
+    pub fn rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.config.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Sets the `User-Agent` header. Defaults to `whatsapp_handler/<version>`.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Builds the client.
+    ///
+    /// # Returns
+    /// The client, or a `reqwest::Error` if the HTTP client cannot be initialized
+    /// (e.g. the TLS backend fails to load).
+    pub fn build(self) -> Result<WhatsAppClient, reqwest::Error> {
+        let mut http = Client::builder().user_agent(self.user_agent);
+        if let Some(timeout) = self.timeout {
+            http = http.timeout(timeout);
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            http = http.connect_timeout(connect_timeout);
+        }
+        if let Some(proxy) = self.proxy {
+            http = http.proxy(proxy);
+        }
+
+        Ok(WhatsAppClient {
+            http: http.build()?,
+            config: Arc::new(self.config),
+        })
+    }
+}
@@ -1,12 +1,19 @@
 use crate::action::incoming;
 use crate::action::outgoing;
+use crate::action::webhook;
+use crate::error::{Diagnostic, IncomingError, SendError, SignatureError, SubscriptionError};
+use crate::formatter::incoming_type::IncomingMessage;
+use crate::formatter::incoming_type::event::WebhookEvent;
+use crate::formatter::incoming_type::statuses::StatusUpdate;
+use crate::formatter::incoming_type::subscription::SubscriptionQuery;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 
-use crate::formatter::outgoing_type::MessageType;
+use crate::formatter::outgoing_type::{MessageType, response::SendResponse};
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryPolicy;
 
 /// Configuration details for communicating with the WhatsApp API.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
     /// Base URL of the WhatsApp API (e.g., https://graph.facebook.com).
     pub whatsapp_base_url: String,
@@ -22,6 +29,22 @@ pub struct Config {
 
     /// System user access token for API authorization.
     pub whatsapp_system_user_token: String,
+
+    /// App secret used to verify the `X-Hub-Signature-256` header of webhook payloads.
+    #[serde(default)]
+    pub whatsapp_app_secret: Option<String>,
+
+    /// Verify token expected in the webhook subscription handshake.
+    #[serde(default)]
+    pub whatsapp_verify_token: Option<String>,
+
+    /// How failed outgoing sends are retried.
+    #[serde(skip)]
+    pub retry_policy: RetryPolicy,
+
+    /// Paces outgoing sends, if set.
+    #[serde(skip)]
+    pub rate_limiter: Option<RateLimiter>,
 }
 
 impl Config {
@@ -36,6 +59,8 @@ impl Config {
     ///
     /// # Example
     /// ```
+    /// use whatsapp_handler::config;
+    ///
     /// let config = config::Config::from(
     ///     "https://graph.facebook.com".to_string(),
     ///     "v17.0".to_string(),
@@ -57,9 +82,141 @@ impl Config {
             whatsapp_business_id,
             whatsapp_phone_number_id,
             whatsapp_system_user_token,
+            whatsapp_app_secret: None,
+            whatsapp_verify_token: None,
+            retry_policy: RetryPolicy::default(),
+            rate_limiter: None,
         }
     }
 
+    /// Sets the app secret used to verify incoming webhook signatures.
+    ///
+    /// # Example
+    /// ```
+    /// use whatsapp_handler::config;
+    ///
+    /// let config = config::Config::from(
+    ///     "https://graph.facebook.com".to_string(),
+    ///     "v17.0".to_string(),
+    ///     "your-business-id".to_string(),
+    ///     "your-phone-id".to_string(),
+    ///     "your-token".to_string(),
+    /// )
+    /// .with_app_secret("your-app-secret".to_string());
+    /// ```
+    pub fn with_app_secret(mut self, whatsapp_app_secret: String) -> Self {
+        self.whatsapp_app_secret = Some(whatsapp_app_secret);
+        self
+    }
+
+    /// Sets the verify token expected in the webhook subscription handshake.
+    pub fn with_verify_token(mut self, whatsapp_verify_token: String) -> Self {
+        self.whatsapp_verify_token = Some(whatsapp_verify_token);
+        self
+    }
+
+    /// Sets how failed outgoing sends are retried. Use `RetryPolicy::none()` to disable retries.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Paces outgoing sends with a rate limiter, so sends over the limit wait instead of
+    /// failing with `130429`. Share one limiter between the configs of the same phone number.
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Answers the GET request Meta sends when the webhook callback URL is registered.
+    ///
+    /// # Arguments
+    /// - `query`: The `hub.mode`, `hub.verify_token` and `hub.challenge` query parameters.
+    ///
+    /// # Returns
+    /// The `hub.challenge` string to echo back, or a `SubscriptionError` if the request
+    /// should be rejected.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let challenge = config.verify_subscription(&query)?;
+    /// ```
+    pub fn verify_subscription(
+        &self,
+        query: &SubscriptionQuery,
+    ) -> Result<String, SubscriptionError> {
+        let verify_token = self
+            .whatsapp_verify_token
+            .as_deref()
+            .ok_or(SubscriptionError::TokenNotConfigured)?;
+        webhook::verify_subscription(verify_token, query)
+    }
+
+    /// Verifies a raw webhook body against its `X-Hub-Signature-256` header.
+    ///
+    /// Call this before `incoming_message` or `incoming_statuses` so that unsigned
+    /// or tampered payloads never reach the parsers.
+    ///
+    /// # Arguments
+    /// - `body`: Raw request body bytes, exactly as received.
+    /// - `signature`: Value of the `X-Hub-Signature-256` header, if present.
+    ///
+    /// # Returns
+    /// The verified body as `&str`, or a `SignatureError` describing why it was rejected.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let payload = config.verify_payload(&body, headers.get("X-Hub-Signature-256"))?;
+    /// let messages = config.incoming_message(payload);
+    /// ```
+    pub fn verify_payload<'a>(
+        &self,
+        body: &'a [u8],
+        signature: Option<&str>,
+    ) -> Result<&'a str, SignatureError> {
+        let app_secret = self
+            .whatsapp_app_secret
+            .as_deref()
+            .ok_or(SignatureError::SecretNotConfigured)?;
+        webhook::verify_signature(app_secret, body, signature)
+    }
+
+    /// Processes any incoming WhatsApp webhook payload in a single pass.
+    ///
+    /// Unlike `incoming_message` and `incoming_statuses`, this does not need to know in advance
+    /// what the payload contains: messages, statuses, errors and non-`messages` fields are all
+    /// returned, in payload order, together with their entry id, field and metadata.
+    ///
+    /// # Arguments
+    /// - `payload`: Raw JSON string received from the WhatsApp webhook.
+    ///
+    /// # Returns
+    /// A `Result` with a tuple containing:
+    /// - `Vec<WebhookEvent>`: List of events in payload order.
+    /// - `Vec<Diagnostic>`: Items or changes that were skipped, with their location.
+    ///
+    /// Returns `Err(IncomingError)` if the payload is not a valid webhook.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let (events, diagnostics) = config.incoming(payload)?;
+    /// for event in events {
+    ///     match event.payload {
+    ///         EventPayload::Message(message) => println!("message: {:?}", message),
+    ///         EventPayload::Status(status) => println!("status: {:?}", status),
+    ///         EventPayload::Error(error) => println!("error: {:?}", error),
+    ///         EventPayload::Other(value) => println!("{}: {:?}", event.field, value),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// ```
+    pub fn incoming(
+        &self,
+        payload: &str,
+    ) -> Result<(Vec<WebhookEvent>, Vec<Diagnostic>), IncomingError> {
+        incoming::find_events(self, payload)
+    }
+
     /// Processes incoming WhatsApp message payloads (typically from webhooks).
     ///
     /// # Arguments
@@ -67,11 +224,14 @@ impl Config {
     ///
     /// # Returns
     /// A `Result` with a tuple containing:
-    /// - `Vec<Value>`: List of parsed message objects.
-    /// - `Vec<String>`: List of errors associated each metadata or extracted details.
+    /// - `Vec<IncomingMessage>`: List of typed messages.
+    /// - `Vec<Diagnostic>`: Messages or changes that were skipped, with their location.
     ///
-    /// Returns `Err(String)` if parsing fails.
-    pub fn incoming_message(self, payload: &str) -> Result<(Vec<Value>, Vec<String>), String> {
+    /// Returns `Err(IncomingError)` if the payload is not a valid message webhook.
+    pub fn incoming_message(
+        &self,
+        payload: &str,
+    ) -> Result<(Vec<IncomingMessage>, Vec<Diagnostic>), IncomingError> {
         incoming::find_messages(self, payload)
     }
 
@@ -82,29 +242,37 @@ impl Config {
     ///
     /// # Returns
     /// A `Result` with a tuple containing:
-    /// - `Vec<Value>`: List of parsed status objects.
-    /// - `Vec<String>`: List of errors associated each metadata or extracted details.
+    /// - `Vec<StatusUpdate>`: List of typed status updates.
+    /// - `Vec<Diagnostic>`: Changes that were skipped, with their location.
     ///
-    /// Returns `Err(String)` if parsing fails.
-    pub fn incoming_statuses(self, payload: &str) -> Result<(Vec<Value>, Vec<String>), String> {
+    /// Returns `Err(IncomingError)` if the payload is not a valid status webhook.
+    pub fn incoming_statuses(
+        &self,
+        payload: &str,
+    ) -> Result<(Vec<StatusUpdate>, Vec<Diagnostic>), IncomingError> {
         incoming::find_statuses(self, payload)
     }
 
     /// Sends an outgoing WhatsApp message using the configured API details.
     ///
+    /// Transient failures are retried according to `retry_policy`, and sends wait for
+    /// `rate_limiter` when one is set.
+    /// Each call opens its own HTTP connection. To reuse connections across sends, or for
+    /// custom timeouts, a proxy or a user agent, build a `client::WhatsAppClient` instead.
+    ///
     /// # Arguments
     /// - `message`: A structured `MessageType` representing the message to send.
     ///
     /// # Returns
     /// A `Result` with:
-    /// - `Ok(Value)`: The API response as a JSON value.
-    /// - `Err(reqwest::Error)`: If the HTTP request fails.
+    /// - `Ok(SendResponse)`: The id of the sent message and the recipient's WhatsApp ID.
+    /// - `Err(SendError)`: If the request fails or the API answers with an error.
     ///
     /// # Example
-    /// ```
+    /// ```ignore
     /// let response = config.outgoing(message).await?;
     /// ```
-    pub async fn outgoing(self, message: MessageType) -> Result<Value, reqwest::Error> {
-        outgoing::send(&self, &message).await
+    pub async fn outgoing(&self, message: MessageType) -> Result<SendResponse, SendError> {
+        outgoing::send(self, &message).await
     }
 }
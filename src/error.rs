@@ -0,0 +1,312 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Errors returned when verifying the `X-Hub-Signature-256` header of a webhook payload.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SignatureError {
+    /// No app secret is configured, so the payload cannot be verified.
+    #[error("app secret is not configured")]
+    SecretNotConfigured,
+
+    /// The request did not carry an `X-Hub-Signature-256` header.
+    #[error("X-Hub-Signature-256 header is missing")]
+    Missing,
+
+    /// The header is not of the form `sha256=<hex digest>`.
+    #[error("X-Hub-Signature-256 header is malformed")]
+    Malformed,
+
+    /// The signature does not match the payload.
+    #[error("X-Hub-Signature-256 does not match the payload")]
+    Mismatch,
+
+    /// The payload was signed correctly but is not valid UTF-8.
+    #[error("payload is not valid UTF-8")]
+    InvalidUtf8,
+}
+
+/// Errors returned when answering the webhook subscription handshake.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SubscriptionError {
+    /// No verify token is configured, so the handshake cannot be answered.
+    #[error("verify token is not configured")]
+    TokenNotConfigured,
+
+    /// A required `hub.*` query parameter was not supplied.
+    #[error("query parameter `{0}` is missing")]
+    MissingParameter(&'static str),
+
+    /// `hub.mode` was something other than `subscribe`.
+    #[error("unsupported hub.mode `{0}`")]
+    UnsupportedMode(String),
+
+    /// `hub.verify_token` does not match the configured token.
+    #[error("hub.verify_token does not match")]
+    TokenMismatch,
+}
+
+/// Errors returned when a webhook payload cannot be parsed.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum IncomingError {
+    /// The payload is not valid JSON.
+    #[error("payload is not valid JSON (line {line}, column {column}): {message}")]
+    Syntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+
+    /// The payload is valid JSON but does not have the expected webhook shape.
+    #[error("unexpected webhook shape at `{path}`: {message}")]
+    Payload {
+        /// JSON path of the offending value (e.g. `entry[0].changes[1].value.metadata`).
+        path: String,
+        /// Index of the entry containing the offending value, if any.
+        entry_index: Option<usize>,
+        /// Index of the change containing the offending value, if any.
+        change_index: Option<usize>,
+        message: String,
+    },
+}
+
+/// A problem with a single item of an otherwise valid webhook payload.
+///
+/// Diagnostics do not abort parsing; the remaining items are still returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// JSON path of the item (e.g. `entry[0].changes[0].value.messages[2]`).
+    pub path: String,
+    /// Index of the entry the item belongs to.
+    pub entry_index: usize,
+    /// Index of the change the item belongs to, if any.
+    pub change_index: Option<usize>,
+    /// Index of the item inside its list, if any.
+    pub item_index: Option<usize>,
+    /// What went wrong.
+    pub kind: DiagnosticKind,
+}
+
+/// The reason a webhook item was skipped.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// The entry id does not match the configured business account.
+    #[error("business account `{0}` is not recognized")]
+    UnknownBusinessAccount(String),
+
+    /// The change's `metadata.phone_number_id` does not match the configured phone number.
+    #[error("phone number id `{0}` is not recognized")]
+    UnknownPhoneNumber(String),
+
+    /// The item has no `type` field.
+    #[error("message has no type")]
+    MissingType,
+
+    /// The item does not have the expected shape.
+    #[error("item could not be parsed: {0}")]
+    Invalid(String),
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at `{}`", self.kind, self.path)
+    }
+}
+
+/// Errors returned when sending through a `TenantRegistry`.
+#[derive(Debug, Error)]
+pub enum TenantError {
+    /// No tenant is registered for the phone number id.
+    #[error("phone number id `{0}` is not registered")]
+    UnknownPhoneNumber(String),
+
+    /// Sending the message failed.
+    #[error(transparent)]
+    Send(#[from] SendError),
+}
+
+/// Errors returned when sending an outgoing message.
+#[derive(Debug, Error)]
+pub enum SendError {
+    /// The request could not be sent or the response could not be read.
+    #[error("request to the WhatsApp API failed: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    /// The API answered with an error status and a body that is not a Graph API error.
+    #[error("WhatsApp API returned HTTP {status}")]
+    Http {
+        status: u16,
+        /// Delay asked for in the `Retry-After` header, if any.
+        retry_after: Option<Duration>,
+        body: String,
+    },
+
+    /// The API answered with success, but the body is not a send response.
+    #[error("unexpected response from the WhatsApp API: {message}")]
+    Decode { body: String, message: String },
+
+    /// The API answered with a Graph API error.
+    #[error("WhatsApp API error {} ({}): {}", .error.code, .error.r#type, .error.message)]
+    Api {
+        status: u16,
+        /// Delay asked for in the `Retry-After` header, if any.
+        retry_after: Option<Duration>,
+        error: Box<GraphApiError>,
+    },
+}
+
+impl SendError {
+    /// Returns `true` if sending the same message again later may succeed.
+    ///
+    /// Connection failures, `429` and `5xx` responses and the throttling and
+    /// temporary-failure codes of `ErrorCode` are retryable; everything else is not.
+    /// Other timeouts are not retryable: the API may already have accepted the message,
+    /// and sending it again would deliver it twice.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SendError::Transport(error) => error.is_connect(),
+            SendError::Http { status, .. } => *status == 429 || *status >= 500,
+            SendError::Decode { .. } => false,
+            SendError::Api { status, error, .. } => *status >= 500 || error.kind().is_retryable(),
+        }
+    }
+
+    /// Returns the delay the API asked to wait before retrying, if any.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            SendError::Http { retry_after, .. } | SendError::Api { retry_after, .. } => {
+                *retry_after
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the Graph API error, if the API sent one.
+    pub fn api_error(&self) -> Option<&GraphApiError> {
+        match self {
+            SendError::Api { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// The `error` object of a failed Graph API response.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GraphApiError {
+    pub message: String,
+    #[serde(rename = "type", default)]
+    pub r#type: String,
+    pub code: u32,
+    pub error_subcode: Option<u32>,
+    /// Extra detail, usually `{ "messaging_product": "whatsapp", "details": "..." }`.
+    pub error_data: Option<Value>,
+    /// Identifier to quote when contacting Meta support.
+    pub fbtrace_id: Option<String>,
+}
+
+impl GraphApiError {
+    /// Returns the well-known meaning of the error code.
+    pub fn kind(&self) -> ErrorCode {
+        ErrorCode::from(self.code)
+    }
+
+    /// Returns `error_data.details`, which explains the error in more depth.
+    pub fn details(&self) -> Option<&str> {
+        self.error_data.as_ref()?.get("details")?.as_str()
+    }
+}
+
+/// Well-known Graph API error codes for outgoing messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// `1`: unknown API error, possibly a temporary server problem.
+    ApiUnknown,
+    /// `2`: temporary API service failure.
+    ApiService,
+    /// `190`: the access token has expired or is invalid.
+    AccessTokenExpired,
+    /// `80007`: the business account hit its rate limit.
+    AccountRateLimit,
+    /// `130429`: the phone number hit its throughput limit.
+    RateLimit,
+    /// `131016`: the service is temporarily unavailable.
+    ServiceUnavailable,
+    /// `131026`: the recipient cannot receive the message (not on WhatsApp, old app, ...).
+    InvalidRecipient,
+    /// `131047`: more than 24 hours passed since the user last replied; use a template.
+    ReEngagementWindow,
+    /// `131048`: sending was restricted for sending too much spam.
+    SpamRateLimit,
+    /// `131056`: too many messages to the same recipient in a short time.
+    PairRateLimit,
+    /// `132000`: the number of template parameters does not match the template.
+    TemplateParameterMismatch,
+    /// `132001`: the template does not exist in that language or is not approved.
+    TemplateNotFound,
+    /// `132015`: the template is paused because of low quality.
+    TemplatePaused,
+    /// `132016`: the template is permanently disabled.
+    TemplateDisabled,
+    /// Any other code.
+    Other(u32),
+}
+
+impl ErrorCode {
+    /// Returns the numeric code.
+    pub fn code(&self) -> u32 {
+        match self {
+            ErrorCode::ApiUnknown => 1,
+            ErrorCode::ApiService => 2,
+            ErrorCode::AccessTokenExpired => 190,
+            ErrorCode::AccountRateLimit => 80007,
+            ErrorCode::RateLimit => 130429,
+            ErrorCode::ServiceUnavailable => 131016,
+            ErrorCode::InvalidRecipient => 131026,
+            ErrorCode::ReEngagementWindow => 131047,
+            ErrorCode::SpamRateLimit => 131048,
+            ErrorCode::PairRateLimit => 131056,
+            ErrorCode::TemplateParameterMismatch => 132000,
+            ErrorCode::TemplateNotFound => 132001,
+            ErrorCode::TemplatePaused => 132015,
+            ErrorCode::TemplateDisabled => 132016,
+            ErrorCode::Other(code) => *code,
+        }
+    }
+
+    /// Returns `true` for throttling and temporary failures, which may succeed later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorCode::ApiUnknown
+                | ErrorCode::ApiService
+                | ErrorCode::AccountRateLimit
+                | ErrorCode::RateLimit
+                | ErrorCode::ServiceUnavailable
+                | ErrorCode::SpamRateLimit
+                | ErrorCode::PairRateLimit
+        )
+    }
+}
+
+impl From<u32> for ErrorCode {
+    fn from(code: u32) -> Self {
+        match code {
+            1 => ErrorCode::ApiUnknown,
+            2 => ErrorCode::ApiService,
+            190 => ErrorCode::AccessTokenExpired,
+            80007 => ErrorCode::AccountRateLimit,
+            130429 => ErrorCode::RateLimit,
+            131016 => ErrorCode::ServiceUnavailable,
+            131026 => ErrorCode::InvalidRecipient,
+            131047 => ErrorCode::ReEngagementWindow,
+            131048 => ErrorCode::SpamRateLimit,
+            131056 => ErrorCode::PairRateLimit,
+            132000 => ErrorCode::TemplateParameterMismatch,
+            132001 => ErrorCode::TemplateNotFound,
+            132015 => ErrorCode::TemplatePaused,
+            132016 => ErrorCode::TemplateDisabled,
+            code => ErrorCode::Other(code),
+        }
+    }
+}
@@ -1,6 +1,277 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+/// Represents all message types that can arrive in a WhatsApp webhook.
+///
+/// Each variant wraps the typed payload produced by `action::incoming::find_messages`,
+/// so callers can `match` on the message kind instead of re-parsing JSON.
+///
+/// Text messages sent from a Click-to-WhatsApp ad are returned as `Ads`; other message
+/// types keep the ad details in their own `referral` field. Text messages that refer to a
+/// catalog product are returned as `Enquiry`. Message types this crate does not model yet
+/// are returned as `Other`, with their raw JSON.
+///
+/// When serialized, the variant name is stored next to the message (`{"kind": "ads",
+/// "data": {...}}`), so a queued message reads back as the same variant.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+pub enum IncomingMessage {
+    Order(order::Order),
+    Text(text::Text),
+    Ads(ads::Ads),
+    Enquiry(enquiry::Enquiry),
+    Unknown(unknown::Unknown),
+    Location(location::Location),
+    Contacts(contact::Contacts),
+    Reaction(reaction::Reaction),
+    Button(button::Button),
+    Interactive(interactive::Interactive),
+    System(system::System),
+    Sticker(sticker::Sticker),
+    Video(video::Video),
+    Audio(audio::Audio),
+    Document(document::Document),
+    Image(image::Image),
+    Other(other::Other),
+}
+
+impl IncomingMessage {
+    /// Returns the reply/forwarding context of the message, whatever its type.
+    pub fn context(&self) -> Option<&context::Context> {
+        match self {
+            IncomingMessage::Order(message) => message.context.as_ref(),
+            IncomingMessage::Text(message) => message.context.as_ref(),
+            IncomingMessage::Ads(message) => message.context.as_ref(),
+            IncomingMessage::Enquiry(message) => Some(&message.context),
+            IncomingMessage::Unknown(message) => message.context.as_ref(),
+            IncomingMessage::Location(message) => message.context.as_ref(),
+            IncomingMessage::Contacts(message) => message.context.as_ref(),
+            IncomingMessage::Reaction(message) => message.context.as_ref(),
+            IncomingMessage::Button(message) => message.context.as_ref(),
+            IncomingMessage::Interactive(message) => message.context.as_ref(),
+            IncomingMessage::System(message) => message.context.as_ref(),
+            IncomingMessage::Sticker(message) => message.context.as_ref(),
+            IncomingMessage::Video(message) => message.context.as_ref(),
+            IncomingMessage::Audio(message) => message.context.as_ref(),
+            IncomingMessage::Document(message) => message.context.as_ref(),
+            IncomingMessage::Image(message) => message.context.as_ref(),
+            IncomingMessage::Other(message) => message.context.as_ref(),
+        }
+    }
+
+    /// Returns the ad or post referral the message came from, whatever its type.
+    pub fn referral(&self) -> Option<&ads::Referral> {
+        match self {
+            IncomingMessage::Order(message) => message.referral.as_ref(),
+            IncomingMessage::Text(message) => message.referral.as_ref(),
+            IncomingMessage::Ads(message) => Some(&message.referral),
+            IncomingMessage::Enquiry(message) => message.referral.as_ref(),
+            IncomingMessage::Unknown(message) => message.referral.as_ref(),
+            IncomingMessage::Location(message) => message.referral.as_ref(),
+            IncomingMessage::Contacts(message) => message.referral.as_ref(),
+            IncomingMessage::Reaction(message) => message.referral.as_ref(),
+            IncomingMessage::Button(message) => message.referral.as_ref(),
+            IncomingMessage::Interactive(message) => message.referral.as_ref(),
+            IncomingMessage::System(message) => message.referral.as_ref(),
+            IncomingMessage::Sticker(message) => message.referral.as_ref(),
+            IncomingMessage::Video(message) => message.referral.as_ref(),
+            IncomingMessage::Audio(message) => message.referral.as_ref(),
+            IncomingMessage::Document(message) => message.referral.as_ref(),
+            IncomingMessage::Image(message) => message.referral.as_ref(),
+            IncomingMessage::Other(message) => message.referral.as_ref(),
+        }
+    }
+
+    /// Returns the sender's profile name and WhatsApp ID, resolved from the change's `contacts`.
+    pub fn sender(&self) -> Option<&full_messages_payload::Contact> {
+        match self {
+            IncomingMessage::Order(message) => message.sender.as_ref(),
+            IncomingMessage::Text(message) => message.sender.as_ref(),
+            IncomingMessage::Ads(message) => message.sender.as_ref(),
+            IncomingMessage::Enquiry(message) => message.sender.as_ref(),
+            IncomingMessage::Unknown(message) => message.sender.as_ref(),
+            IncomingMessage::Location(message) => message.sender.as_ref(),
+            IncomingMessage::Contacts(message) => message.sender.as_ref(),
+            IncomingMessage::Reaction(message) => message.sender.as_ref(),
+            IncomingMessage::Button(message) => message.sender.as_ref(),
+            IncomingMessage::Interactive(message) => message.sender.as_ref(),
+            IncomingMessage::System(message) => message.sender.as_ref(),
+            IncomingMessage::Sticker(message) => message.sender.as_ref(),
+            IncomingMessage::Video(message) => message.sender.as_ref(),
+            IncomingMessage::Audio(message) => message.sender.as_ref(),
+            IncomingMessage::Document(message) => message.sender.as_ref(),
+            IncomingMessage::Image(message) => message.sender.as_ref(),
+            IncomingMessage::Other(message) => message.sender.as_ref(),
+        }
+    }
+
+    /// Returns the business phone number that received the message.
+    pub fn metadata(&self) -> Option<&full_messages_payload::Metadata> {
+        match self {
+            IncomingMessage::Order(message) => message.metadata.as_ref(),
+            IncomingMessage::Text(message) => message.metadata.as_ref(),
+            IncomingMessage::Ads(message) => message.metadata.as_ref(),
+            IncomingMessage::Enquiry(message) => message.metadata.as_ref(),
+            IncomingMessage::Unknown(message) => message.metadata.as_ref(),
+            IncomingMessage::Location(message) => message.metadata.as_ref(),
+            IncomingMessage::Contacts(message) => message.metadata.as_ref(),
+            IncomingMessage::Reaction(message) => message.metadata.as_ref(),
+            IncomingMessage::Button(message) => message.metadata.as_ref(),
+            IncomingMessage::Interactive(message) => message.metadata.as_ref(),
+            IncomingMessage::System(message) => message.metadata.as_ref(),
+            IncomingMessage::Sticker(message) => message.metadata.as_ref(),
+            IncomingMessage::Video(message) => message.metadata.as_ref(),
+            IncomingMessage::Audio(message) => message.metadata.as_ref(),
+            IncomingMessage::Document(message) => message.metadata.as_ref(),
+            IncomingMessage::Image(message) => message.metadata.as_ref(),
+            IncomingMessage::Other(message) => message.metadata.as_ref(),
+        }
+    }
+
+    /// Returns the sender's profile name, if WhatsApp shared it.
+    pub fn sender_name(&self) -> Option<&str> {
+        self.sender()
+            .map(|sender| sender.profile.name.as_str())
+            .filter(|name| !name.is_empty())
+    }
+}
+
+/// Module for the Unix-seconds **Timestamp** carried by incoming messages and status updates.
+pub mod timestamp {
+    use super::*;
+    use std::fmt;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    /// A WhatsApp timestamp: Unix seconds, sent as a string.
+    ///
+    /// The original value is always kept. Values that are not valid Unix seconds do not
+    /// fail parsing; `unix_seconds` and `to_system_time` return `None` for them instead.
+    ///
+    /// Timestamps order by time first; malformed values sort before valid ones.
+    #[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct Timestamp {
+        seconds: Option<u64>,
+        raw: String,
+    }
+
+    impl Timestamp {
+        /// Creates a timestamp from Unix seconds.
+        pub fn from_unix(seconds: u64) -> Self {
+            Timestamp {
+                seconds: Some(seconds),
+                raw: seconds.to_string(),
+            }
+        }
+
+        /// Reads a timestamp from a JSON string or number.
+        pub fn from_value(value: &Value) -> Self {
+            match value {
+                Value::String(raw) => Timestamp::from(raw.as_str()),
+                Value::Number(number) => match number.as_u64() {
+                    Some(seconds) => Timestamp::from_unix(seconds),
+                    None => Timestamp {
+                        seconds: None,
+                        raw: number.to_string(),
+                    },
+                },
+                _ => Timestamp::default(),
+            }
+        }
+
+        /// Returns the value exactly as WhatsApp sent it.
+        pub fn as_str(&self) -> &str {
+            &self.raw
+        }
+
+        /// Returns the number of seconds since the Unix epoch, or `None` if malformed.
+        pub fn unix_seconds(&self) -> Option<u64> {
+            self.seconds
+        }
+
+        /// Returns `true` if the value is a valid Unix timestamp.
+        pub fn is_valid(&self) -> bool {
+            self.seconds.is_some()
+        }
+
+        /// Converts the timestamp to a `SystemTime`, or `None` if malformed.
+        pub fn to_system_time(&self) -> Option<SystemTime> {
+            UNIX_EPOCH.checked_add(Duration::from_secs(self.seconds?))
+        }
+
+        /// Returns how long ago the timestamp was, or `None` if malformed or in the future.
+        pub fn elapsed(&self) -> Option<Duration> {
+            self.to_system_time()?.elapsed().ok()
+        }
+
+        /// Converts the timestamp to a UTC `chrono::DateTime`, or `None` if malformed.
+        #[cfg(feature = "chrono")]
+        pub fn to_datetime(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+            chrono::DateTime::from_timestamp(i64::try_from(self.seconds?).ok()?, 0)
+        }
+    }
+
+    impl From<&str> for Timestamp {
+        fn from(raw: &str) -> Self {
+            Timestamp {
+                seconds: raw.trim().parse().ok(),
+                raw: raw.to_string(),
+            }
+        }
+    }
+
+    impl From<SystemTime> for Timestamp {
+        fn from(time: SystemTime) -> Self {
+            let seconds = time
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default();
+            Timestamp::from_unix(seconds)
+        }
+    }
+
+    impl fmt::Display for Timestamp {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(&self.raw)
+        }
+    }
+
+    impl Serialize for Timestamp {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&self.raw)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Timestamp {
+        /// Accepts a string or a number; any other value yields a malformed timestamp.
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ok(Timestamp::from_value(&Value::deserialize(deserializer)?))
+        }
+    }
+}
+
+/// Module for the **Context** and **Referral** blocks shared by every incoming message.
+pub mod context {
+    use super::*;
+
+    /// Context of an incoming message: the quoted message, forwarding flags and referred product.
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    pub struct Context {
+        /// Sender of the quoted message.
+        pub from: Option<String>,
+        /// ID of the quoted message.
+        pub id: Option<String>,
+        /// Whether the message was forwarded.
+        #[serde(default)]
+        pub forwarded: bool,
+        /// Whether the message was forwarded more than 5 times.
+        #[serde(default)]
+        pub frequently_forwarded: bool,
+        /// Catalog product the customer is asking about.
+        pub referred_product: Option<enquiry::ReferredProduct>,
+    }
+}
+
 /// Module for handling WhatsApp **Order** messages.
 pub mod order {
     use super::*;
@@ -11,10 +282,13 @@ pub mod order {
         pub from: String,
         pub id: String,
         pub order: OrderDetails,
-        pub context: MessageContext,
-        pub timestamp: String,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub timestamp: timestamp::Timestamp,
         #[serde(rename = "type")]
         pub r#type: String,
+        pub referral: Option<ads::Referral>,
     }
 
     /// Details of the order (catalog and products).
@@ -33,29 +307,26 @@ pub mod order {
         pub item_price: String,
         pub currency: String,
     }
-
-    /// Context of the related message.
-    #[derive(Debug, Serialize, Deserialize)]
-    pub struct MessageContext {
-        pub from: String,
-        pub id: String,
-    }
 }
 
 /// Module for handling **Product Enquiry** messages.
 pub mod enquiry {
     use super::*;
 
-    /// Represents an incoming enquiry message.
+    /// Represents a text message sent about a catalog product.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct Enquiry {
         pub from: String,
         pub id: String,
         pub text: EnquiryPayload,
-        pub context: EnquiryContext,
-        pub timestamp: String,
+        /// Always carries `referred_product` for an enquiry.
+        pub context: context::Context,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub timestamp: timestamp::Timestamp,
         #[serde(rename = "type")]
         pub r#type: String,
+        pub referral: Option<ads::Referral>,
     }
 
     /// Payload containing the enquiry body.
@@ -64,14 +335,6 @@ pub mod enquiry {
         pub body: String,
     }
 
-    /// Context of the enquiry including the referred product.
-    #[derive(Debug, Serialize, Deserialize)]
-    pub struct EnquiryContext {
-        pub from: String,
-        pub id: String,
-        pub referred_product: ReferredProduct,
-    }
-
     /// Details of the product the enquiry refers to.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct ReferredProduct {
@@ -84,15 +347,22 @@ pub mod enquiry {
 pub mod unknown {
     use super::*;
 
-    /// Represents an unknown or error message.
+    /// Represents a message WhatsApp could not deliver (type `unsupported` or `unknown`),
+    /// with the reason in `errors` and the original JSON in `raw`.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct Unknown {
         pub from: String,
         pub id: String,
-        pub timestamp: String,
+        pub timestamp: timestamp::Timestamp,
         pub errors: Vec<ErrorDetail>,
         #[serde(rename = "type")]
         pub r#type: String,
+        /// The message exactly as received.
+        pub raw: Value,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub referral: Option<ads::Referral>,
     }
 
     /// Details about the error.
@@ -104,34 +374,81 @@ pub mod unknown {
     }
 }
 
+/// Module for message types this crate does not model yet.
+pub mod other {
+    use super::*;
+
+    /// A message whose `type` is not recognized, kept so it can be logged or handled by hand.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Other {
+        pub from: String,
+        pub id: String,
+        pub timestamp: timestamp::Timestamp,
+        #[serde(rename = "type")]
+        pub r#type: String,
+        /// The message exactly as received.
+        pub raw: Value,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub referral: Option<ads::Referral>,
+    }
+
+    impl Other {
+        /// Deserializes the raw message into a type of your own.
+        pub fn raw_as<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+            T::deserialize(&self.raw)
+        }
+    }
+}
+
 /// Module for handling **Ads Referral** messages.
 pub mod ads {
     use super::*;
 
-    /// Represents a referral ad message.
+    /// Represents a text message sent from a Click-to-WhatsApp ad or post.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct Ads {
         pub referral: Referral,
         pub from: String,
         pub id: String,
-        pub timestamp: String,
+        pub timestamp: timestamp::Timestamp,
         #[serde(rename = "type")]
         pub r#type: String,
         pub text: TextBody,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
     }
 
     /// Details about the referral source.
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Default, Serialize, Deserialize)]
     pub struct Referral {
         pub source_url: String,
+        /// ID of the ad or post.
         pub source_id: String,
+        /// Either `ad` or `post`.
         pub source_type: String,
         pub headline: String,
         pub body: String,
+        /// Either `image` or `video`.
         pub media_type: String,
-        pub image_url: String,
-        pub video_url: String,
-        pub thumbnail_url: String,
+        /// Present when `media_type` is `image`.
+        pub image_url: Option<String>,
+        /// Present when `media_type` is `video`.
+        pub video_url: Option<String>,
+        /// Present when `media_type` is `video`.
+        pub thumbnail_url: Option<String>,
+        /// Click ID used for conversion attribution.
+        pub ctwa_clid: Option<String>,
+        /// Welcome message shown to the user when they opened the chat.
+        pub welcome_message: Option<WelcomeMessage>,
+    }
+
+    /// Welcome message configured on the ad.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct WelcomeMessage {
+        pub text: String,
     }
 
     /// Text body of the ad.
@@ -150,17 +467,58 @@ pub mod location {
     pub struct Location {
         pub from: String,
         pub id: String,
-        pub timestamp: String,
+        pub timestamp: timestamp::Timestamp,
         pub location: LocationPayload,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub referral: Option<ads::Referral>,
     }
 
     /// Details of the location shared.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct LocationPayload {
-        pub latitude: String,
-        pub longitude: String,
-        pub name: String,
-        pub address: String,
+        /// Latitude in decimal degrees.
+        pub latitude: f64,
+        /// Longitude in decimal degrees.
+        pub longitude: f64,
+        /// Name of the place, when the user shared a place rather than a pin.
+        pub name: Option<String>,
+        pub address: Option<String>,
+        /// Website of the place, if any.
+        pub url: Option<String>,
+    }
+
+    /// Mean radius of the Earth in metres, as used by the haversine formula.
+    const EARTH_RADIUS_METRES: f64 = 6_371_008.8;
+
+    impl LocationPayload {
+        /// Returns the great-circle distance in metres to another point.
+        ///
+        /// # Arguments
+        ///
+        /// * `latitude` - Latitude of the other point in decimal degrees.
+        /// * `longitude` - Longitude of the other point in decimal degrees.
+        pub fn distance_to(&self, latitude: f64, longitude: f64) -> f64 {
+            let (lat1, lat2) = (self.latitude.to_radians(), latitude.to_radians());
+            let delta_lat = lat2 - lat1;
+            let delta_lon = (longitude - self.longitude).to_radians();
+
+            let a = (delta_lat / 2.0).sin().powi(2)
+                + lat1.cos() * lat2.cos() * (delta_lon / 2.0).sin().powi(2);
+            2.0 * EARTH_RADIUS_METRES * a.sqrt().asin()
+        }
+
+        /// Returns `true` if the location is within `radius` metres of a point.
+        ///
+        /// # Arguments
+        ///
+        /// * `latitude` - Latitude of the centre in decimal degrees.
+        /// * `longitude` - Longitude of the centre in decimal degrees.
+        /// * `radius` - Radius of the area in metres.
+        pub fn is_within(&self, latitude: f64, longitude: f64, radius: f64) -> bool {
+            self.distance_to(latitude, longitude) <= radius
+        }
     }
 }
 
@@ -168,7 +526,22 @@ pub mod location {
 pub mod contact {
     use super::*;
 
-    /// Represents a contact card message.
+    /// Represents a message carrying one or more contact cards.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Contacts {
+        pub from: String,
+        pub id: String,
+        pub timestamp: timestamp::Timestamp,
+        #[serde(rename = "type")]
+        pub r#type: String,
+        pub contacts: Vec<Contact>,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub referral: Option<ads::Referral>,
+    }
+
+    /// Represents a single contact card.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct Contact {
         pub addresses: Vec<Address>,
@@ -247,9 +620,13 @@ pub mod reaction {
     pub struct Reaction {
         pub from: String,
         pub id: String,
-        pub timestamp: String,
+        pub timestamp: timestamp::Timestamp,
         pub r#type: String,
         pub reaction: ReactionPayload,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub referral: Option<ads::Referral>,
     }
 
     /// Details of the reaction.
@@ -267,27 +644,172 @@ pub mod button {
     /// Represents a button click message.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct Button {
-        pub context: MsgBtnContext,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
         pub from: String,
         pub id: String,
-        pub timestamp: String,
+        pub timestamp: timestamp::Timestamp,
         #[serde(rename = "type")]
         pub r#type: String,
         pub button: ButtonDetails,
+        pub referral: Option<ads::Referral>,
     }
 
-    /// Context of the button message.
+    /// Details of the button clicked.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct ButtonDetails {
+        pub text: String,
+        pub payload: String,
+    }
+}
+
+/// Module for handling **Interactive** reply messages (button and list replies).
+pub mod interactive {
+    use super::*;
+
+    /// Represents a reply to an interactive button or list message.
     #[derive(Debug, Serialize, Deserialize)]
-    pub struct MsgBtnContext {
+    pub struct Interactive {
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
         pub from: String,
         pub id: String,
+        pub timestamp: timestamp::Timestamp,
+        #[serde(rename = "type")]
+        pub r#type: String,
+        pub interactive: InteractiveReply,
+        pub referral: Option<ads::Referral>,
     }
 
-    /// Details of the button clicked.
+    /// The reply chosen by the user, tagged by `interactive.type`.
     #[derive(Debug, Serialize, Deserialize)]
-    pub struct ButtonDetails {
-        pub text: String,
-        pub payload: String,
+    #[serde(tag = "type", rename_all = "snake_case")]
+    pub enum InteractiveReply {
+        /// Reply to an `interactive_button::InteractiveButton` message.
+        ButtonReply { button_reply: ButtonReply },
+        /// Reply to an `interactive_list::InteractiveList` message.
+        ListReply { list_reply: ListReply },
+        /// Response submitted from a WhatsApp Flow.
+        NfmReply { nfm_reply: FlowReply },
+    }
+
+    /// Details of the button tapped.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct ButtonReply {
+        pub id: String,
+        pub title: String,
+    }
+
+    /// Details of the list row selected.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct ListReply {
+        pub id: String,
+        pub title: String,
+        pub description: String,
+    }
+
+    /// Details of a submitted WhatsApp Flow.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct FlowReply {
+        pub name: String,
+        pub body: String,
+        /// The flow response, as a JSON-encoded string.
+        pub response_json: String,
+    }
+
+    impl FlowReply {
+        /// Decodes `response_json` into a raw JSON value.
+        pub fn response(&self) -> Result<Value, serde_json::Error> {
+            serde_json::from_str(&self.response_json)
+        }
+
+        /// Decodes `response_json` into a caller-chosen type.
+        ///
+        /// # Example
+        /// ```ignore
+        /// #[derive(Deserialize)]
+        /// struct Signup {
+        ///     flow_token: String,
+        ///     email: String,
+        /// }
+        ///
+        /// let signup: Signup = flow_reply.response_as()?;
+        /// ```
+        pub fn response_as<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+            serde_json::from_str(&self.response_json)
+        }
+    }
+}
+
+/// Module for handling **System** messages (number and identity changes).
+pub mod system {
+    use super::*;
+
+    /// Represents a system message sent when a customer changes number or identity.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct System {
+        pub from: String,
+        pub id: String,
+        pub timestamp: timestamp::Timestamp,
+        #[serde(rename = "type")]
+        pub r#type: String,
+        pub system: SystemPayload,
+        /// Present for `customer_identity_changed` messages.
+        pub identity: Option<Identity>,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub referral: Option<ads::Referral>,
+    }
+
+    /// Details of the system event.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct SystemPayload {
+        pub body: String,
+        /// Event type (`user_changed_number`, `customer_identity_changed`).
+        #[serde(rename = "type")]
+        pub r#type: String,
+        /// New WhatsApp ID of the customer, for `user_changed_number`.
+        pub wa_id: Option<String>,
+        /// New WhatsApp ID of the customer, as sent by older API versions.
+        pub new_wa_id: Option<String>,
+        /// Identity hash of the customer, for `customer_identity_changed`.
+        pub identity: Option<String>,
+        /// WhatsApp ID of the customer the event is about.
+        pub customer: Option<String>,
+    }
+
+    /// Identity details sent with a `customer_identity_changed` message.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Identity {
+        pub acknowledged: bool,
+        pub created_timestamp: timestamp::Timestamp,
+        pub hash: String,
+    }
+
+    impl System {
+        /// WhatsApp ID the customer used before the change.
+        pub fn old_wa_id(&self) -> &str {
+            self.system.customer.as_deref().unwrap_or(&self.from)
+        }
+
+        /// WhatsApp ID the customer uses after a `user_changed_number` event.
+        pub fn new_wa_id(&self) -> Option<&str> {
+            self.system
+                .new_wa_id
+                .as_deref()
+                .or(self.system.wa_id.as_deref())
+        }
+
+        /// Identity hash of the customer after a `customer_identity_changed` event.
+        pub fn identity_hash(&self) -> Option<&str> {
+            self.identity
+                .as_ref()
+                .map(|identity| identity.hash.as_str())
+                .or(self.system.identity.as_deref())
+        }
     }
 }
 
@@ -300,15 +822,20 @@ pub mod sticker {
     pub struct Sticker {
         pub from: String,
         pub id: String,
-        pub timestamp: String,
+        pub timestamp: timestamp::Timestamp,
         pub r#type: String, // escape Rust keyword 'type'
         pub sticker: StickerData,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub referral: Option<ads::Referral>,
     }
 
     /// Details of the sticker.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct StickerData {
         pub id: String,
+        /// Whether the sticker is animated.
         pub animated: bool,
         pub mime_type: String,
         pub sha256: String,
@@ -324,15 +851,19 @@ pub mod video {
     pub struct Video {
         pub from: String,
         pub id: String,
-        pub timestamp: String,
+        pub timestamp: timestamp::Timestamp,
         pub r#type: String,
-        pub document: VideoData,
+        pub video: VideoData,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub referral: Option<ads::Referral>,
     }
 
     /// Details of the video.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct VideoData {
-        pub caption: String,
+        pub caption: Option<String>,
         pub mime_type: String,
         pub sha256: String,
         pub id: String,
@@ -348,18 +879,23 @@ pub mod audio {
     pub struct Audio {
         pub from: String,
         pub id: String,
-        pub timestamp: String,
+        pub timestamp: timestamp::Timestamp,
         pub r#type: String,
-        pub document: AudioData,
+        pub audio: AudioData,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub referral: Option<ads::Referral>,
     }
 
     /// Details of the audio file.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct AudioData {
-        pub caption: String,
         pub mime_type: String,
         pub sha256: String,
         pub id: String,
+        /// Whether the audio is a voice note recorded in WhatsApp.
+        pub voice: bool,
     }
 }
 
@@ -372,15 +908,21 @@ pub mod document {
     pub struct Document {
         pub from: String,
         pub id: String,
-        pub timestamp: String,
+        pub timestamp: timestamp::Timestamp,
         pub r#type: String,
         pub document: DocumentData,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub referral: Option<ads::Referral>,
     }
 
     /// Details of the document.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct DocumentData {
-        pub caption: String,
+        pub caption: Option<String>,
+        /// Name of the file as sent by the user.
+        pub filename: Option<String>,
         pub mime_type: String,
         pub sha256: String,
         pub id: String,
@@ -396,15 +938,19 @@ pub mod image {
     pub struct Image {
         pub from: String,
         pub id: String,
-        pub timestamp: String,
+        pub timestamp: timestamp::Timestamp,
         pub r#type: String,
         pub image: ImageData,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub referral: Option<ads::Referral>,
     }
 
     /// Details of the image.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct ImageData {
-        pub caption: String,
+        pub caption: Option<String>,
         pub mime_type: String,
         pub sha256: String,
         pub id: String,
@@ -420,9 +966,13 @@ pub mod text {
     pub struct Text {
         pub from: String,
         pub id: String,
-        pub timestamp: String,
+        pub timestamp: timestamp::Timestamp,
         pub r#type: String, // use `r#type` because `type` is a reserved Rust keyword
         pub text: TextPayload,
+        pub context: Option<context::Context>,
+        pub sender: Option<full_messages_payload::Contact>,
+        pub metadata: Option<full_messages_payload::Metadata>,
+        pub referral: Option<ads::Referral>,
     }
 
     /// Details of the text body.
@@ -439,18 +989,59 @@ pub mod statuses {
     /// Represents a status update event.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct StatusUpdate {
-        /// Unique ID of the status.
+        /// ID of the message the status refers to.
         pub id: String,
         /// Optional recipient ID.
         pub recipient_id: Option<String>,
-        /// Status string (e.g., delivered, read).
-        pub status: String,
+        /// Delivery status of the message.
+        pub status: Status,
         /// Timestamp of the event.
-        pub timestamp: String,
+        pub timestamp: timestamp::Timestamp,
         /// Optional conversation details.
         pub conversation: Option<Conversation>,
         /// Optional pricing information.
         pub pricing: Option<Pricing>,
+        /// Reasons the message failed, present when `status` is `Failed`.
+        #[serde(default)]
+        pub errors: Vec<StatusError>,
+        /// Arbitrary string attached to the outgoing message, echoed back for tracking.
+        pub biz_opaque_callback_data: Option<String>,
+    }
+
+    /// Delivery status of an outgoing message.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Status {
+        Sent,
+        Delivered,
+        Read,
+        Failed,
+        Deleted,
+        /// A status not known to this version of the crate.
+        #[serde(other)]
+        Unknown,
+    }
+
+    /// An error explaining why a message failed.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct StatusError {
+        /// Graph API error code (e.g. 131047).
+        pub code: u32,
+        /// Short description of the error.
+        pub title: String,
+        /// Error message, identical to `title` on most API versions.
+        pub message: Option<String>,
+        /// Additional details about the error.
+        pub error_data: Option<StatusErrorData>,
+        /// Link to the error code documentation.
+        pub href: Option<String>,
+    }
+
+    /// Additional details about a status error.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct StatusErrorData {
+        /// Human readable explanation of the error.
+        pub details: String,
     }
 
     /// Details about the conversation.
@@ -458,8 +1049,8 @@ pub mod statuses {
     pub struct Conversation {
         /// Conversation ID.
         pub id: String,
-        /// Expiration timestamp for the conversation.
-        pub expiration_timestamp: String,
+        /// Expiration timestamp for the conversation, sent only with the first `sent` status.
+        pub expiration_timestamp: Option<timestamp::Timestamp>,
         /// Origin details.
         pub origin: Origin,
     }
@@ -475,74 +1066,205 @@ pub mod statuses {
     /// Pricing details for the message.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct Pricing {
-        /// Pricing model (e.g., CBP).
+        /// Pricing model (e.g., CBP, PMP).
         pub pricing_model: String,
         /// Whether the message is billable.
+        #[serde(default)]
         pub billable: bool,
         /// Category of the message (user_initiated, business_initiated, referral_conversion).
         pub category: String,
     }
 }
 
-/// Module representing the full webhook payload for statuses.
-pub mod full_statuses_payload {
+/// Module for **Message Template** webhooks (status, quality and category updates).
+pub mod template {
     use super::*;
 
-    /// Top-level webhook payload for status events.
-    #[derive(Debug, Deserialize, Serialize)]
-    pub struct WebhookStatusesPayload {
-        /// Object type (usually "whatsapp_business_account").
-        pub object: String,
-        /// List of entries in the payload.
-        pub entry: Vec<Entry>,
+    /// Sent under the `message_template_status_update` field when a template is reviewed,
+    /// paused or disabled.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct TemplateStatusUpdate {
+        /// New status of the template.
+        pub event: TemplateStatus,
+        pub message_template_id: u64,
+        pub message_template_name: String,
+        pub message_template_language: String,
+        /// Why the template was rejected (e.g. `INCORRECT_CATEGORY`), or `NONE`.
+        pub reason: Option<String>,
+        /// Extra explanation sent when a template is paused or disabled.
+        pub other_info: Option<OtherInfo>,
     }
 
-    /// An individual entry in the payload.
-    #[derive(Debug, Deserialize, Serialize)]
-    pub struct Entry {
-        /// Entry ID.
-        pub id: String,
-        /// List of changes inside the entry.
-        pub changes: Vec<Change>,
+    /// Status of a message template.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+    pub enum TemplateStatus {
+        Approved,
+        Rejected,
+        Pending,
+        Paused,
+        Disabled,
+        Flagged,
+        Reinstated,
+        PendingDeletion,
+        InAppeal,
+        /// A status not known to this version of the crate.
+        #[serde(other)]
+        Unknown,
     }
 
-    /// Represents a change event in the entry.
-    #[derive(Debug, Deserialize, Serialize)]
-    pub struct Change {
-        /// Change value details.
-        pub value: Vaalue,
-        /// Field name (usually "statuses").
-        pub field: String,
+    /// Extra explanation attached to a template status update.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct OtherInfo {
+        pub title: String,
+        pub description: String,
     }
 
-    /// Details of the change value.
-    #[derive(Debug, Deserialize, Serialize)]
-    pub struct Vaalue {
-        /// Messaging product type (usually "whatsapp").
-        pub messaging_product: String,
-        /// Metadata details.
-        pub metadata: Metadata,
-        /// List of status updates.
-        pub statuses: Vec<Value>,
+    /// Sent under the `message_template_quality_update` field when a template's quality
+    /// rating changes.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct TemplateQualityUpdate {
+        /// Previous quality rating (`GREEN`, `YELLOW`, `RED`, `UNKNOWN`).
+        pub previous_quality_score: String,
+        /// New quality rating (`GREEN`, `YELLOW`, `RED`, `UNKNOWN`).
+        pub new_quality_score: String,
+        pub message_template_id: u64,
+        pub message_template_name: String,
+        pub message_template_language: String,
     }
 
-    /// Metadata about the phone number.
-    #[derive(Debug, Deserialize, Serialize)]
-    pub struct Metadata {
-        /// Displayed phone number.
+    /// Sent under the `template_category_update` field when Meta recategorizes a template.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct TemplateCategoryUpdate {
+        pub message_template_id: u64,
+        pub message_template_name: String,
+        pub message_template_language: String,
+        /// Category before the update (`MARKETING`, `UTILITY`, `AUTHENTICATION`).
+        pub previous_category: Option<String>,
+        /// Category after the update.
+        pub new_category: Option<String>,
+        /// Category the template will be moved to, when announced ahead of time.
+        pub correct_category: Option<String>,
+    }
+}
+
+/// Module for **Account** webhooks (phone number quality, account updates and alerts).
+pub mod account {
+    use super::*;
+
+    /// Sent under the `phone_number_quality_update` field when a phone number's quality
+    /// rating or messaging limit changes.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct PhoneNumberQualityUpdate {
         pub display_phone_number: String,
-        /// Internal phone number ID.
-        pub phone_number_id: String,
+        pub event: QualityEvent,
+        /// Messaging limit tier now in effect (e.g. `TIER_1K`, `TIER_UNLIMITED`).
+        pub current_limit: String,
+        /// Messaging limit tier before the change, if sent.
+        pub old_limit: Option<String>,
+    }
+
+    impl PhoneNumberQualityUpdate {
+        /// Returns `true` when the phone number was moved to a lower messaging limit
+        /// or flagged for low quality.
+        pub fn is_degraded(&self) -> bool {
+            matches!(self.event, QualityEvent::Downgrade | QualityEvent::Flagged)
+        }
+    }
+
+    /// What happened to the phone number's quality rating.
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+    pub enum QualityEvent {
+        Flagged,
+        Unflagged,
+        Downgrade,
+        Upgrade,
+        Onboarding,
+        /// An event not known to this version of the crate.
+        #[serde(other)]
+        Unknown,
+    }
+
+    /// Sent under the `account_update` field when the business account is verified,
+    /// restricted, banned or otherwise changed.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct AccountUpdate {
+        /// Kind of update (e.g. `VERIFIED_ACCOUNT`, `ACCOUNT_VIOLATION`, `DISABLED_UPDATE`).
+        pub event: String,
+        pub phone_number: Option<String>,
+        pub ban_info: Option<BanInfo>,
+        pub violation_info: Option<ViolationInfo>,
+        #[serde(default)]
+        pub restriction_info: Vec<RestrictionInfo>,
+    }
+
+    /// Ban state of the business account.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct BanInfo {
+        pub waba_ban_state: String,
+        pub waba_ban_date: Option<String>,
+    }
+
+    /// The policy the business account violated.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct ViolationInfo {
+        pub violation_type: String,
+    }
+
+    /// A restriction placed on the business account.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct RestrictionInfo {
+        /// Restricted capability (e.g. `RESTRICTED_ADD_PHONE_NUMBER_ACTION`).
+        pub restriction_type: String,
+        /// When the restriction is lifted.
+        pub expiration: Option<String>,
+    }
+
+    /// Sent under the `account_alerts` field for alerts shown in WhatsApp Manager.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct AccountAlert {
+        /// Kind of entity the alert is about (e.g. `PHONE_NUMBER`, `WABA`).
+        pub entity_type: String,
+        /// ID of the entity; Meta sends it as a string or a number.
+        #[serde(deserialize_with = "string_or_number")]
+        pub entity_id: String,
+        pub alert_info: AlertInfo,
+    }
+
+    /// Details of an account alert.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct AlertInfo {
+        /// Severity of the alert (e.g. `CRITICAL`, `WARNING`, `INFORMATIONAL`).
+        pub alert_severity: String,
+        /// Whether the alert is `ACTIVE` or `NONE`.
+        pub alert_status: String,
+        pub alert_type: String,
+        pub alert_description: String,
+    }
+
+    /// Reads an id that may be sent as a JSON string or number.
+    fn string_or_number<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<String, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::String(id) => Ok(id),
+            Value::Number(id) => Ok(id.to_string()),
+            other => Err(serde::de::Error::custom(format!(
+                "expected a string or number id, got {}",
+                other
+            ))),
+        }
     }
 }
 
-/// Module representing the full webhook payload for incoming messages.
-pub mod full_messages_payload {
+/// Module representing the full webhook envelope for any field.
+pub mod webhook_payload {
     use super::*;
 
-    /// Top-level webhook payload for message events.
+    /// Top-level webhook payload, as POSTed by Meta for every subscribed field.
     #[derive(Debug, Deserialize, Serialize)]
-    pub struct WebhookMessagePayload {
+    pub struct WebhookPayload {
         /// Object type (usually "whatsapp_business_account").
         pub object: String,
         /// List of entries in the payload.
@@ -552,7 +1274,7 @@ pub mod full_messages_payload {
     /// An individual entry in the payload.
     #[derive(Debug, Deserialize, Serialize)]
     pub struct Entry {
-        /// Entry ID.
+        /// Entry ID (the WhatsApp Business Account ID).
         pub id: String,
         /// List of changes inside the entry.
         pub changes: Vec<Change>,
@@ -561,11 +1283,16 @@ pub mod full_messages_payload {
     /// Represents a change event in the entry.
     #[derive(Debug, Deserialize, Serialize)]
     pub struct Change {
-        /// Change value details.
-        pub value: Vaalue,
-        /// Field name (usually "messages").
+        /// Raw change value; its shape depends on `field`.
+        pub value: Value,
+        /// Field name (e.g. "messages", "message_template_status_update").
         pub field: String,
     }
+}
+
+/// Module representing the `value` of a `messages` webhook change.
+pub mod full_messages_payload {
+    use super::*;
 
     /// Details of the change value.
     #[derive(Debug, Deserialize, Serialize)]
@@ -575,13 +1302,21 @@ pub mod full_messages_payload {
         /// Metadata details.
         pub metadata: Metadata,
         /// List of contacts involved.
+        #[serde(default)]
         pub contacts: Vec<Contact>,
         /// List of message events.
+        #[serde(default)]
         pub messages: Vec<Value>,
+        /// List of status updates.
+        #[serde(default)]
+        pub statuses: Vec<Value>,
+        /// List of errors that could not be tied to a message.
+        #[serde(default)]
+        pub errors: Vec<Value>,
     }
 
     /// Metadata about the phone number.
-    #[derive(Debug, Deserialize, Serialize)]
+    #[derive(Debug, Clone, Deserialize, Serialize)]
     pub struct Metadata {
         /// Displayed phone number.
         pub display_phone_number: String,
@@ -590,18 +1325,89 @@ pub mod full_messages_payload {
     }
 
     /// Contact details for the message.
-    #[derive(Debug, Deserialize, Serialize)]
+    ///
+    /// Missing fields are left empty, so incomplete contact data does not hold back the
+    /// messages of the change.
+    #[derive(Debug, Clone, Deserialize, Serialize)]
     pub struct Contact {
         /// Profile information.
+        #[serde(default)]
         pub profile: Profile,
         /// WhatsApp ID of the contact.
+        #[serde(default)]
         pub wa_id: String,
     }
 
     /// User profile details.
-    #[derive(Debug, Deserialize, Serialize)]
+    #[derive(Debug, Clone, Default, Deserialize, Serialize)]
     pub struct Profile {
-        /// Contact's name.
+        /// Contact's name, empty when WhatsApp did not share it.
+        #[serde(default)]
         pub name: String,
     }
 }
+
+/// Module for events extracted from a webhook payload.
+pub mod event {
+    use super::full_messages_payload::Metadata;
+    use super::*;
+
+    /// A single event extracted from a webhook payload, in payload order.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct WebhookEvent {
+        /// ID of the entry (WhatsApp Business Account) the event belongs to.
+        pub entry_id: String,
+        /// Webhook field the event was delivered under (e.g. "messages").
+        pub field: String,
+        /// Phone number metadata, present for `messages` field events.
+        pub metadata: Option<Metadata>,
+        /// The event itself.
+        pub payload: EventPayload,
+    }
+
+    /// The content of a webhook event.
+    #[derive(Debug, Serialize, Deserialize)]
+    #[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+    #[allow(clippy::large_enum_variant)]
+    pub enum EventPayload {
+        /// An incoming message from `value.messages`.
+        Message(IncomingMessage),
+        /// A message status update from `value.statuses`.
+        Status(statuses::StatusUpdate),
+        /// An error from `value.errors` that is not tied to a message.
+        Error(unknown::ErrorDetail),
+        /// A `message_template_status_update` field.
+        TemplateStatus(template::TemplateStatusUpdate),
+        /// A `message_template_quality_update` field.
+        TemplateQuality(template::TemplateQualityUpdate),
+        /// A `template_category_update` field.
+        TemplateCategory(template::TemplateCategoryUpdate),
+        /// A `phone_number_quality_update` field.
+        PhoneNumberQuality(account::PhoneNumberQualityUpdate),
+        /// An `account_update` field.
+        AccountUpdate(account::AccountUpdate),
+        /// An `account_alerts` field.
+        AccountAlert(account::AccountAlert),
+        /// The raw `value` of a field this crate does not model.
+        Other(Value),
+    }
+}
+
+/// Module for the webhook **Subscription** handshake.
+pub mod subscription {
+    use super::*;
+
+    /// Query parameters Meta sends with the GET request when a callback URL is registered.
+    #[derive(Debug, Default, Deserialize, Serialize)]
+    pub struct SubscriptionQuery {
+        /// Always `subscribe` for a verification request.
+        #[serde(rename = "hub.mode")]
+        pub mode: Option<String>,
+        /// The verify token entered in the app dashboard.
+        #[serde(rename = "hub.verify_token")]
+        pub verify_token: Option<String>,
+        /// Value that must be echoed back to complete the handshake.
+        #[serde(rename = "hub.challenge")]
+        pub challenge: Option<String>,
+    }
+}
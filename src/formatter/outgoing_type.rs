@@ -19,6 +19,23 @@ pub enum MessageType {
     Template(template::Template),
 }
 
+impl MessageType {
+    /// Returns the phone number or WhatsApp ID the message is addressed to.
+    pub fn recipient(&self) -> &str {
+        match self {
+            MessageType::InteractiveButton(message) => &message.to,
+            MessageType::InteractiveList(message) => &message.to,
+            MessageType::Sticker(message) => &message.to,
+            MessageType::Video(message) => &message.to,
+            MessageType::Audio(message) => &message.to,
+            MessageType::Document(message) => &message.to,
+            MessageType::Image(message) => &message.to,
+            MessageType::Text(message) => &message.to,
+            MessageType::Template(message) => &message.to,
+        }
+    }
+}
+
 /// Module for interactive button messages.
 pub mod interactive_button {
     use super::*;
@@ -469,4 +486,64 @@ pub mod template {
     pub enum MType {
         template,
     }
-}
\ No newline at end of file
+}
+/// Module for the response to a successful send.
+pub mod response {
+    use super::*;
+
+    /// Body returned by `POST /{phone_number_id}/messages` when a message is accepted.
+    ///
+    /// The message `id` is the same `wamid` that later status webhooks carry in
+    /// `StatusUpdate::id`, so it can be stored to correlate them.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    pub struct SendResponse {
+        pub messaging_product: String,
+        #[serde(default)]
+        pub contacts: Vec<SentContact>,
+        #[serde(default)]
+        pub messages: Vec<SentMessage>,
+    }
+
+    impl SendResponse {
+        /// Returns the `wamid` of the sent message.
+        pub fn message_id(&self) -> Option<&str> {
+            self.messages.first().map(|message| message.id.as_str())
+        }
+
+        /// Returns the WhatsApp ID the recipient's phone number resolved to.
+        pub fn wa_id(&self) -> Option<&str> {
+            self.contacts.first().map(|contact| contact.wa_id.as_str())
+        }
+    }
+
+    /// A recipient of the sent message.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    pub struct SentContact {
+        /// The phone number or WhatsApp ID the message was sent to.
+        pub input: String,
+        /// The recipient's WhatsApp ID.
+        pub wa_id: String,
+    }
+
+    /// The message that was sent.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    pub struct SentMessage {
+        /// The `wamid` of the message.
+        pub id: String,
+        /// Sent for template messages only.
+        pub message_status: Option<MessageStatus>,
+    }
+
+    /// Whether a template message was accepted for delivery.
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum MessageStatus {
+        Accepted,
+        /// The template is being checked for quality before it is delivered.
+        HeldForQualityAssessment,
+        Paused,
+        /// A status not known to this version of the crate.
+        #[serde(other)]
+        Unknown,
+    }
+}
@@ -1,11 +1,138 @@
 pub mod action;
+pub mod client;
 pub mod config;
+pub mod error;
 pub mod formatter;
+pub mod rate_limit;
+pub mod retry;
+pub mod tenant;
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Config matching the ids used in the webhook fixtures below.
+    fn fixture_config() -> config::Config {
+        config::Config::from(
+            String::from("https://graph.facebook.com"),
+            String::from("v17.0"),
+            String::from("whatsapp_business_id"),
+            String::from("whatsapp_phone_number_id"),
+            String::from(""),
+        )
+    }
+
+    /// Wraps a `messages` array fixture in a full webhook envelope.
+    fn messages_fixture(messages: &str) -> String {
+        format!(
+            r#"{{
+    "object": "whatsapp_business_account",
+    "entry": [
+        {{
+            "id": "whatsapp_business_id",
+            "changes": [
+                {{
+                    "value": {{
+                        "messaging_product": "whatsapp",
+                        "metadata": {{
+                            "display_phone_number": "15550783881",
+                            "phone_number_id": "whatsapp_phone_number_id"
+                        }},
+                        "contacts": [
+                            {{
+                                "profile": {{
+                                    "name": "Jane"
+                                }},
+                                "wa_id": "16315551234"
+                            }}
+                        ],
+                        "messages": {}
+                    }},
+                    "field": "messages"
+                }}
+            ]
+        }}
+    ]
+}}"#,
+            messages
+        )
+    }
+
+    /// Serves `(status, extra header lines, body)` responses in order on a local port.
+    ///
+    /// Returns the base URL of the server and the number of requests it has answered.
+    async fn mock_graph_api(
+        responses: Vec<(u16, &'static str, &'static str)>,
+    ) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        use std::sync::{Arc, atomic::AtomicUsize, atomic::Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+
+        tokio::spawn(async move {
+            for (status, headers, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+
+                // Read the headers, then as much body as Content-Length announces.
+                let mut request = Vec::new();
+                let mut chunk = [0u8; 1024];
+                loop {
+                    let read = socket.read(&mut chunk).await.unwrap();
+                    request.extend_from_slice(&chunk[..read]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some(header_end) = text.find("\r\n\r\n") {
+                        let content_length = text[..header_end]
+                            .lines()
+                            .find_map(|line| {
+                                let (name, value) = line.split_once(':')?;
+                                name.eq_ignore_ascii_case("content-length")
+                                    .then(|| value.trim().parse::<usize>().ok())?
+                            })
+                            .unwrap_or(0);
+                        if request.len() >= header_end + 4 + content_length {
+                            break;
+                        }
+                    }
+                    if read == 0 {
+                        break;
+                    }
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+                    status,
+                    body.len(),
+                    headers,
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.ok();
+            }
+        });
+
+        (base_url, requests)
+    }
+
+    /// A text message to send in outgoing tests.
+    fn text_message() -> formatter::outgoing_type::MessageType {
+        use formatter::outgoing_type::text::{Content, MType, Text};
+
+        formatter::outgoing_type::MessageType::Text(Text {
+            to: String::from("16315551234"),
+            messaging_product: String::from("whatsapp"),
+            recipient_type: String::from("individual"),
+            r#type: MType::text,
+            text: Content {
+                preview_url: false,
+                body: String::from("hello"),
+            },
+        })
+    }
+
     #[tokio::test]
     #[ignore] // remove ignore incase you want to test
     async fn incoming_message() {
@@ -304,4 +431,2135 @@ mod tests {
         .await;
         println!("Outgoing template message: {:?}", response)
     }
+
+    #[test]
+    fn verify_webhook_signature() {
+        use error::SignatureError;
+
+        let config = config::Config::from(
+            String::from("https://graph.facebook.com"),
+            String::from("v17.0"),
+            String::from("whatsapp_business_id"),
+            String::from("whatsapp_phone_number_id"),
+            String::from(""),
+        )
+        .with_app_secret(String::from("app_secret"));
+
+        let body = br#"{"object":"whatsapp_business_account","entry":[]}"#;
+        // HMAC-SHA256 of `body` keyed with "app_secret".
+        let signature = "sha256=c236c758c9b2bd40b388a550eeca09eaca72384dd0aa5e7c31d19cddac5bba46";
+
+        assert_eq!(
+            config.verify_payload(body, None),
+            Err(SignatureError::Missing)
+        );
+        assert_eq!(
+            config.verify_payload(body, Some("md5=abc")),
+            Err(SignatureError::Malformed)
+        );
+        assert_eq!(
+            config.verify_payload(body, Some(&format!("sha256={}", "0".repeat(64)))),
+            Err(SignatureError::Mismatch)
+        );
+        assert!(config.verify_payload(body, Some(signature)).is_ok());
+    }
+
+    #[test]
+    fn verify_webhook_subscription() {
+        use error::SubscriptionError;
+        use formatter::incoming_type::subscription::SubscriptionQuery;
+
+        let config = config::Config::from(
+            String::from("https://graph.facebook.com"),
+            String::from("v17.0"),
+            String::from("whatsapp_business_id"),
+            String::from("whatsapp_phone_number_id"),
+            String::from(""),
+        )
+        .with_verify_token(String::from("verify_token"));
+
+        let query: SubscriptionQuery = serde_json::from_str(
+            r#"{"hub.mode":"subscribe","hub.verify_token":"verify_token","hub.challenge":"1158201444"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.verify_subscription(&query),
+            Ok(String::from("1158201444"))
+        );
+
+        let query = SubscriptionQuery {
+            verify_token: Some(String::from("wrong_token")),
+            ..query
+        };
+        assert_eq!(
+            config.verify_subscription(&query),
+            Err(SubscriptionError::TokenMismatch)
+        );
+
+        assert_eq!(
+            config.verify_subscription(&SubscriptionQuery::default()),
+            Err(SubscriptionError::MissingParameter("hub.mode"))
+        );
+    }
+
+    #[test]
+    fn incoming_typed_message() {
+        use formatter::incoming_type::IncomingMessage;
+
+        let incoming_stringify = r#"
+{
+    "object": "whatsapp_business_account",
+    "entry": [
+        {
+            "id": "whatsapp_business_id",
+            "changes": [
+                {
+                    "value": {
+                        "messaging_product": "whatsapp",
+                        "metadata": {
+                            "display_phone_number": "PHONE_NUMBER",
+                            "phone_number_id": "whatsapp_phone_number_id"
+                        },
+                        "contacts": [
+                            {
+                                "profile": {
+                                    "name": "Jane"
+                                },
+                                "wa_id": "16315551234"
+                            }
+                        ],
+                        "messages": [
+                            {
+                                "from": "16315551234",
+                                "id": "wamid.text",
+                                "timestamp": "1683229471",
+                                "text": {
+                                    "body": "Hello there!"
+                                },
+                                "type": "text"
+                            },
+                            {
+                                "from": "16315551234",
+                                "id": "wamid.reaction",
+                                "timestamp": "1683229472",
+                                "reaction": {
+                                    "message_id": "wamid.text",
+                                    "emoji": "👍"
+                                },
+                                "type": "reaction"
+                            }
+                        ]
+                    },
+                    "field": "messages"
+                }
+            ]
+        }
+    ]
+}
+"#;
+
+        let (messages, errors) = config::Config::from(
+            String::from("https://graph.facebook.com"),
+            String::from("v17.0"),
+            String::from("whatsapp_business_id"),
+            String::from("whatsapp_phone_number_id"),
+            String::from(""),
+        )
+        .incoming_message(incoming_stringify)
+        .unwrap();
+
+        assert!(errors.is_empty());
+        assert_eq!(messages.len(), 2);
+        match &messages[0] {
+            IncomingMessage::Text(text) => {
+                assert_eq!(text.id, "wamid.text");
+                assert_eq!(text.text.body, "Hello there!");
+            }
+            other => panic!("expected a text message, got {:?}", other),
+        }
+        match &messages[1] {
+            IncomingMessage::Reaction(reaction) => {
+                assert_eq!(reaction.reaction.message_id, "wamid.text");
+            }
+            other => panic!("expected a reaction message, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn incoming_message_errors() {
+        use error::{DiagnosticKind, IncomingError};
+
+        let config = || {
+            config::Config::from(
+                String::from("https://graph.facebook.com"),
+                String::from("v17.0"),
+                String::from("whatsapp_business_id"),
+                String::from("whatsapp_phone_number_id"),
+                String::from(""),
+            )
+        };
+
+        assert!(matches!(
+            config().incoming_message("{ not json"),
+            Err(IncomingError::Syntax { line: 1, .. })
+        ));
+
+        let missing_metadata = r#"
+{
+    "object": "whatsapp_business_account",
+    "entry": [
+        {
+            "id": "whatsapp_business_id",
+            "changes": [
+                {
+                    "value": {
+                        "messaging_product": "whatsapp",
+                        "statuses": []
+                    },
+                    "field": "messages"
+                },
+                {
+                    "value": {
+                        "messaging_product": "whatsapp",
+                        "metadata": {
+                            "display_phone_number": "PHONE_NUMBER",
+                            "phone_number_id": "whatsapp_phone_number_id"
+                        },
+                        "contacts": [],
+                        "messages": [
+                            {
+                                "from": "16315551234",
+                                "id": "wamid.after_malformed",
+                                "timestamp": "1683229471",
+                                "text": { "body": "still delivered" },
+                                "type": "text"
+                            }
+                        ]
+                    },
+                    "field": "messages"
+                }
+            ]
+        }
+    ]
+}
+"#;
+        let (messages, errors) = config().incoming_message(missing_metadata).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(
+            &messages[0],
+            formatter::incoming_type::IncomingMessage::Text(text) if text.id == "wamid.after_malformed"
+        ));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "entry[0].changes[0].value");
+        assert_eq!(errors[0].entry_index, 0);
+        assert_eq!(errors[0].change_index, Some(0));
+        assert!(matches!(errors[0].kind, DiagnosticKind::Invalid(_)));
+
+        let missing_entry_id =
+            r#"{ "object": "whatsapp_business_account", "entry": [{ "changes": [] }] }"#;
+        match config().incoming_message(missing_entry_id) {
+            Err(IncomingError::Payload {
+                path, entry_index, ..
+            }) => {
+                assert_eq!(path, "entry[0]");
+                assert_eq!(entry_index, Some(0));
+            }
+            other => panic!("expected a payload error, got {:?}", other),
+        }
+
+        let untyped = r#"
+{
+    "object": "whatsapp_business_account",
+    "entry": [
+        {
+            "id": "whatsapp_business_id",
+            "changes": [
+                {
+                    "value": {
+                        "messaging_product": "whatsapp",
+                        "metadata": {
+                            "display_phone_number": "PHONE_NUMBER",
+                            "phone_number_id": "whatsapp_phone_number_id"
+                        },
+                        "contacts": [],
+                        "messages": [
+                            {
+                                "from": "16315551234",
+                                "id": "wamid.untyped",
+                                "timestamp": "1683229471"
+                            }
+                        ]
+                    },
+                    "field": "messages"
+                }
+            ]
+        }
+    ]
+}
+"#;
+        let (messages, errors) = config().incoming_message(untyped).unwrap();
+        assert!(messages.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "entry[0].changes[0].value.messages[0]");
+        assert_eq!(errors[0].item_index, Some(0));
+        assert_eq!(errors[0].kind, DiagnosticKind::MissingType);
+    }
+
+    #[test]
+    fn incoming_mixed_events() {
+        use formatter::incoming_type::{IncomingMessage, event::EventPayload};
+
+        let incoming_stringify = r#"
+{
+    "object": "whatsapp_business_account",
+    "entry": [
+        {
+            "id": "whatsapp_business_id",
+            "changes": [
+                {
+                    "value": {
+                        "messaging_product": "whatsapp",
+                        "metadata": {
+                            "display_phone_number": "15550783881",
+                            "phone_number_id": "whatsapp_phone_number_id"
+                        },
+                        "contacts": [
+                            {
+                                "profile": {
+                                    "name": "Jane"
+                                },
+                                "wa_id": "16315551234"
+                            }
+                        ],
+                        "messages": [
+                            {
+                                "from": "16315551234",
+                                "id": "wamid.text",
+                                "timestamp": "1683229471",
+                                "text": {
+                                    "body": "Hello there!"
+                                },
+                                "type": "text"
+                            }
+                        ],
+                        "statuses": [
+                            {
+                                "id": "wamid.sent",
+                                "status": "read",
+                                "timestamp": "1683229472",
+                                "recipient_id": "16315551234"
+                            }
+                        ],
+                        "errors": [
+                            {
+                                "code": 131000,
+                                "title": "Something went wrong",
+                                "error_data": {
+                                    "details": "Unknown error"
+                                }
+                            }
+                        ]
+                    },
+                    "field": "messages"
+                },
+                {
+                    "value": {
+                        "event": "APPROVED",
+                        "message_template_id": 594425479261596,
+                        "message_template_name": "order_update",
+                        "message_template_language": "en_US",
+                        "reason": "NONE"
+                    },
+                    "field": "message_template_status_update"
+                }
+            ]
+        }
+    ]
+}
+"#;
+
+        let (events, errors) = config::Config::from(
+            String::from("https://graph.facebook.com"),
+            String::from("v17.0"),
+            String::from("whatsapp_business_id"),
+            String::from("whatsapp_phone_number_id"),
+            String::from(""),
+        )
+        .incoming(incoming_stringify)
+        .unwrap();
+
+        assert!(errors.is_empty());
+        assert_eq!(events.len(), 4);
+        assert!(matches!(
+            events[0].payload,
+            EventPayload::Message(IncomingMessage::Text(_))
+        ));
+        assert_eq!(
+            events[0].metadata.as_ref().unwrap().display_phone_number,
+            "15550783881"
+        );
+        assert!(matches!(events[1].payload, EventPayload::Status(_)));
+        match &events[2].payload {
+            EventPayload::Error(error) => {
+                assert_eq!(error.code, 131000);
+                assert_eq!(error.details, "Unknown error");
+            }
+            other => panic!("expected an error event, got {:?}", other),
+        }
+        assert_eq!(events[3].field, "message_template_status_update");
+        assert!(events[3].metadata.is_none());
+        assert!(matches!(events[3].payload, EventPayload::TemplateStatus(_)));
+    }
+
+    #[test]
+    fn incoming_interactive_replies() {
+        use formatter::incoming_type::{IncomingMessage, interactive::InteractiveReply};
+
+        let payload = messages_fixture(
+            r#"[
+    {
+        "context": {
+            "from": "15550783881",
+            "id": "wamid.button_prompt"
+        },
+        "from": "16315551234",
+        "id": "wamid.button_reply",
+        "timestamp": "1683229471",
+        "type": "interactive",
+        "interactive": {
+            "type": "button_reply",
+            "button_reply": {
+                "id": "confirm",
+                "title": "Confirm"
+            }
+        }
+    },
+    {
+        "context": {
+            "from": "15550783881",
+            "id": "wamid.list_prompt"
+        },
+        "from": "16315551234",
+        "id": "wamid.list_reply",
+        "timestamp": "1683229472",
+        "type": "interactive",
+        "interactive": {
+            "type": "list_reply",
+            "list_reply": {
+                "id": "billing",
+                "title": "Billing",
+                "description": "View your billing information"
+            }
+        }
+    }
+]"#,
+        );
+
+        let (messages, errors) = fixture_config().incoming_message(&payload).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(messages.len(), 2);
+
+        match &messages[0] {
+            IncomingMessage::Interactive(message) => {
+                assert_eq!(
+                    messages[0]
+                        .context()
+                        .and_then(|context| context.id.as_deref()),
+                    Some("wamid.button_prompt")
+                );
+                match &message.interactive {
+                    InteractiveReply::ButtonReply { button_reply } => {
+                        assert_eq!(button_reply.id, "confirm");
+                        assert_eq!(button_reply.title, "Confirm");
+                    }
+                    other => panic!("expected a button reply, got {:?}", other),
+                }
+            }
+            other => panic!("expected an interactive message, got {:?}", other),
+        }
+        match &messages[1] {
+            IncomingMessage::Interactive(message) => {
+                assert_eq!(
+                    messages[1]
+                        .context()
+                        .and_then(|context| context.id.as_deref()),
+                    Some("wamid.list_prompt")
+                );
+                match &message.interactive {
+                    InteractiveReply::ListReply { list_reply } => {
+                        assert_eq!(list_reply.id, "billing");
+                        assert_eq!(list_reply.description, "View your billing information");
+                    }
+                    other => panic!("expected a list reply, got {:?}", other),
+                }
+            }
+            other => panic!("expected an interactive message, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn incoming_flow_response() {
+        use formatter::incoming_type::{IncomingMessage, interactive::InteractiveReply};
+        use serde::Deserialize;
+
+        #[derive(Deserialize)]
+        struct Signup {
+            flow_token: String,
+            email: String,
+        }
+
+        let payload = messages_fixture(
+            r#"[
+    {
+        "context": {
+            "from": "15550783881",
+            "id": "wamid.flow_prompt"
+        },
+        "from": "16315551234",
+        "id": "wamid.flow_reply",
+        "timestamp": "1683229471",
+        "type": "interactive",
+        "interactive": {
+            "type": "nfm_reply",
+            "nfm_reply": {
+                "name": "flow",
+                "body": "Sent",
+                "response_json": "{\"flow_token\": \"signup-42\", \"email\": \"jane@example.com\"}"
+            }
+        }
+    }
+]"#,
+        );
+
+        let (messages, errors) = fixture_config().incoming_message(&payload).unwrap();
+        assert!(errors.is_empty());
+
+        let IncomingMessage::Interactive(message) = &messages[0] else {
+            panic!("expected an interactive message, got {:?}", messages[0]);
+        };
+        let InteractiveReply::NfmReply { nfm_reply } = &message.interactive else {
+            panic!("expected a flow reply, got {:?}", message.interactive);
+        };
+
+        assert_eq!(nfm_reply.name, "flow");
+        assert_eq!(nfm_reply.body, "Sent");
+        assert_eq!(nfm_reply.response().unwrap()["flow_token"], "signup-42");
+
+        let signup: Signup = nfm_reply.response_as().unwrap();
+        assert_eq!(signup.flow_token, "signup-42");
+        assert_eq!(signup.email, "jane@example.com");
+    }
+
+    #[test]
+    fn incoming_system_messages() {
+        use formatter::incoming_type::IncomingMessage;
+
+        let payload = messages_fixture(
+            r#"[
+    {
+        "from": "16315551234",
+        "id": "wamid.changed_number",
+        "timestamp": "1683229471",
+        "type": "system",
+        "system": {
+            "body": "Jane changed from 16315551234 to 16315559876",
+            "wa_id": "16315559876",
+            "type": "user_changed_number"
+        }
+    },
+    {
+        "from": "16315559876",
+        "id": "wamid.changed_identity",
+        "timestamp": "1683229472",
+        "type": "system",
+        "system": {
+            "body": "Jane's security code changed",
+            "customer": "16315559876",
+            "type": "customer_identity_changed"
+        },
+        "identity": {
+            "acknowledged": true,
+            "created_timestamp": "1683229400",
+            "hash": "t3lJCdHa1v4="
+        }
+    }
+]"#,
+        );
+
+        let (messages, errors) = fixture_config().incoming_message(&payload).unwrap();
+        assert!(errors.is_empty());
+
+        let IncomingMessage::System(changed_number) = &messages[0] else {
+            panic!("expected a system message, got {:?}", messages[0]);
+        };
+        assert_eq!(changed_number.system.r#type, "user_changed_number");
+        assert_eq!(changed_number.old_wa_id(), "16315551234");
+        assert_eq!(changed_number.new_wa_id(), Some("16315559876"));
+
+        let IncomingMessage::System(changed_identity) = &messages[1] else {
+            panic!("expected a system message, got {:?}", messages[1]);
+        };
+        assert_eq!(changed_identity.system.r#type, "customer_identity_changed");
+        assert_eq!(changed_identity.old_wa_id(), "16315559876");
+        assert_eq!(changed_identity.identity_hash(), Some("t3lJCdHa1v4="));
+    }
+
+    #[test]
+    fn incoming_context_and_referral() {
+        use formatter::incoming_type::IncomingMessage;
+
+        let payload = messages_fixture(
+            r#"[
+    {
+        "context": {
+            "forwarded": true,
+            "frequently_forwarded": true
+        },
+        "from": "16315551234",
+        "id": "wamid.forwarded",
+        "timestamp": "1683229471",
+        "type": "image",
+        "image": {
+            "caption": "Look at this",
+            "mime_type": "image/jpeg",
+            "sha256": "IMAGE_HASH",
+            "id": "IMAGE_ID"
+        }
+    },
+    {
+        "context": {
+            "from": "15550783881",
+            "id": "wamid.quoted"
+        },
+        "referral": {
+            "source_url": "https://fb.me/ad",
+            "source_id": "ADS_ID",
+            "source_type": "ad",
+            "headline": "Summer sale",
+            "body": "Up to 50% off",
+            "media_type": "image",
+            "image_url": "https://example.com/ad.jpg",
+            "thumbnail_url": "https://example.com/thumb.jpg"
+        },
+        "from": "16315551234",
+        "id": "wamid.location",
+        "timestamp": "1683229472",
+        "type": "location",
+        "location": {
+            "latitude": "6.5244",
+            "longitude": "3.3792",
+            "name": "Shop",
+            "address": "1 Marina Road"
+        }
+    },
+    {
+        "from": "16315551234",
+        "id": "wamid.contacts",
+        "timestamp": "1683229473",
+        "type": "contacts",
+        "contacts": [
+            {
+                "name": {
+                    "formatted_name": "John Doe",
+                    "first_name": "John"
+                },
+                "phones": [
+                    {
+                        "phone": "+1 631-555-9876",
+                        "wa_id": "16315559876",
+                        "type": "CELL"
+                    }
+                ]
+            }
+        ]
+    }
+]"#,
+        );
+
+        let (messages, errors) = fixture_config().incoming_message(&payload).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(messages.len(), 3);
+
+        let context = messages[0].context().unwrap();
+        assert!(context.forwarded);
+        assert!(context.frequently_forwarded);
+        assert!(context.id.is_none());
+        assert!(messages[0].referral().is_none());
+
+        let context = messages[1].context().unwrap();
+        assert_eq!(context.id.as_deref(), Some("wamid.quoted"));
+        assert!(!context.forwarded);
+        let referral = messages[1].referral().unwrap();
+        assert_eq!(referral.source_id, "ADS_ID");
+        assert_eq!(referral.headline, "Summer sale");
+
+        let IncomingMessage::Contacts(contacts) = &messages[2] else {
+            panic!("expected a contacts message, got {:?}", messages[2]);
+        };
+        assert!(contacts.context.is_none());
+        assert_eq!(contacts.contacts[0].name.formatted_name, "John Doe");
+        assert_eq!(contacts.contacts[0].phones[0].wa_id, "16315559876");
+    }
+
+    #[test]
+    fn incoming_events_round_trip() {
+        use formatter::incoming_type::{
+            IncomingMessage,
+            event::{EventPayload, WebhookEvent},
+        };
+
+        let payload = messages_fixture(
+            r#"[
+    {
+        "referral": {
+            "source_url": "https://fb.me/ad",
+            "source_id": "ADS_ID",
+            "source_type": "ad",
+            "headline": "Summer sale",
+            "body": "Up to 50% off",
+            "media_type": "image",
+            "image_url": "https://example.com/ad.jpg",
+            "ctwa_clid": "ARAkLkA8rmlFeiCktEJQ"
+        },
+        "from": "16315551234",
+        "id": "wamid.ad",
+        "timestamp": "1683229471",
+        "type": "text",
+        "text": {
+            "body": "I'd like to know more"
+        }
+    },
+    {
+        "from": "16315551234",
+        "id": "wamid.reply",
+        "text": {
+            "body": "Thanks!"
+        },
+        "context": {
+            "from": "15550783881",
+            "id": "wamid.previous"
+        },
+        "timestamp": "1683229472",
+        "type": "text"
+    },
+    {
+        "from": "16315551234",
+        "id": "wamid.enquiry",
+        "text": {
+            "body": "Is this available in blue?"
+        },
+        "context": {
+            "from": "15550783881",
+            "id": "wamid.catalog",
+            "referred_product": {
+                "catalog_id": "CATALOG_ID",
+                "product_retailer_id": "SKU-123"
+            }
+        },
+        "timestamp": "1683229473",
+        "type": "text"
+    }
+]"#,
+        );
+
+        let (events, errors) = fixture_config().incoming(&payload).unwrap();
+        assert!(errors.is_empty());
+
+        let queued = serde_json::to_string(&events).unwrap();
+        let events: Vec<WebhookEvent> = serde_json::from_str(&queued).unwrap();
+        let messages: Vec<_> = events
+            .iter()
+            .filter_map(|event| match &event.payload {
+                EventPayload::Message(message) => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(messages.len(), 3);
+
+        let IncomingMessage::Ads(ad) = messages[0] else {
+            panic!("expected an ad referral message, got {:?}", messages[0]);
+        };
+        assert_eq!(
+            ad.referral.ctwa_clid.as_deref(),
+            Some("ARAkLkA8rmlFeiCktEJQ")
+        );
+        let IncomingMessage::Text(reply) = messages[1] else {
+            panic!("expected a text message, got {:?}", messages[1]);
+        };
+        assert_eq!(reply.text.body, "Thanks!");
+        let IncomingMessage::Enquiry(enquiry) = messages[2] else {
+            panic!("expected a product enquiry, got {:?}", messages[2]);
+        };
+        let product = enquiry.context.referred_product.as_ref().unwrap();
+        assert_eq!(product.product_retailer_id, "SKU-123");
+    }
+
+    #[test]
+    fn incoming_click_to_whatsapp_ad() {
+        use formatter::incoming_type::IncomingMessage;
+
+        let payload = messages_fixture(
+            r#"[
+    {
+        "referral": {
+            "source_url": "https://fb.me/ad",
+            "source_id": "ADS_ID",
+            "source_type": "ad",
+            "headline": "Summer sale",
+            "body": "Up to 50% off",
+            "media_type": "video",
+            "video_url": "https://example.com/ad.mp4",
+            "thumbnail_url": "https://example.com/thumb.jpg",
+            "ctwa_clid": "ARAkLkA8rmlFeiCktEJQ",
+            "welcome_message": {
+                "text": "Hi! How can we help?"
+            }
+        },
+        "from": "16315551234",
+        "id": "wamid.ad",
+        "timestamp": "1683229471",
+        "type": "text",
+        "text": {
+            "body": "I'd like to know more"
+        }
+    }
+]"#,
+        );
+
+        let (messages, errors) = fixture_config().incoming_message(&payload).unwrap();
+        assert!(errors.is_empty());
+
+        let IncomingMessage::Ads(ad) = &messages[0] else {
+            panic!("expected an ad referral message, got {:?}", messages[0]);
+        };
+        assert_eq!(ad.text.body, "I'd like to know more");
+        assert_eq!(ad.referral.source_id, "ADS_ID");
+        assert_eq!(
+            ad.referral.ctwa_clid.as_deref(),
+            Some("ARAkLkA8rmlFeiCktEJQ")
+        );
+        assert_eq!(
+            ad.referral.video_url.as_deref(),
+            Some("https://example.com/ad.mp4")
+        );
+        assert!(ad.referral.image_url.is_none());
+        assert_eq!(
+            ad.referral.welcome_message.as_ref().unwrap().text,
+            "Hi! How can we help?"
+        );
+    }
+
+    #[test]
+    fn incoming_product_enquiry() {
+        use formatter::incoming_type::IncomingMessage;
+
+        let payload = messages_fixture(
+            r#"[
+    {
+        "from": "16315551234",
+        "id": "wamid.enquiry",
+        "text": {
+            "body": "Is this available in blue?"
+        },
+        "context": {
+            "from": "15550783881",
+            "id": "wamid.catalog",
+            "referred_product": {
+                "catalog_id": "CATALOG_ID",
+                "product_retailer_id": "SKU-123"
+            }
+        },
+        "timestamp": "1683229471",
+        "type": "text"
+    }
+]"#,
+        );
+
+        let (messages, errors) = fixture_config().incoming_message(&payload).unwrap();
+        assert!(errors.is_empty());
+
+        let IncomingMessage::Enquiry(enquiry) = &messages[0] else {
+            panic!("expected a product enquiry, got {:?}", messages[0]);
+        };
+        assert_eq!(enquiry.text.body, "Is this available in blue?");
+        let product = enquiry.context.referred_product.as_ref().unwrap();
+        assert_eq!(product.catalog_id, "CATALOG_ID");
+        assert_eq!(product.product_retailer_id, "SKU-123");
+    }
+
+    #[test]
+    fn incoming_typed_statuses() {
+        use formatter::incoming_type::statuses::Status;
+
+        let payload = r#"
+{
+    "object": "whatsapp_business_account",
+    "entry": [
+        {
+            "id": "whatsapp_business_id",
+            "changes": [
+                {
+                    "value": {
+                        "messaging_product": "whatsapp",
+                        "metadata": {
+                            "display_phone_number": "15550783881",
+                            "phone_number_id": "whatsapp_phone_number_id"
+                        },
+                        "statuses": [
+                            {
+                                "id": "wamid.sent",
+                                "status": "sent",
+                                "timestamp": "1683229471",
+                                "recipient_id": "16315551234",
+                                "biz_opaque_callback_data": "order-42",
+                                "conversation": {
+                                    "id": "CONVERSATION_ID",
+                                    "expiration_timestamp": "1683315871",
+                                    "origin": {
+                                        "type": "marketing"
+                                    }
+                                },
+                                "pricing": {
+                                    "billable": true,
+                                    "pricing_model": "CBP",
+                                    "category": "marketing"
+                                }
+                            },
+                            {
+                                "id": "wamid.failed",
+                                "status": "failed",
+                                "timestamp": "1683229472",
+                                "recipient_id": "16315551234",
+                                "errors": [
+                                    {
+                                        "code": 131047,
+                                        "title": "Re-engagement message",
+                                        "message": "Re-engagement message",
+                                        "error_data": {
+                                            "details": "Message failed to send because more than 24 hours have passed since the customer last replied to this number."
+                                        },
+                                        "href": "https://developers.facebook.com/docs/whatsapp/cloud-api/support/error-codes/"
+                                    }
+                                ]
+                            }
+                        ]
+                    },
+                    "field": "messages"
+                }
+            ]
+        }
+    ]
+}
+"#;
+
+        let (statuses, errors) = fixture_config().incoming_statuses(payload).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(statuses.len(), 2);
+
+        let sent = &statuses[0];
+        assert_eq!(sent.status, Status::Sent);
+        assert_eq!(sent.biz_opaque_callback_data.as_deref(), Some("order-42"));
+        let conversation = sent.conversation.as_ref().unwrap();
+        assert_eq!(conversation.origin.origin_type, "marketing");
+        assert_eq!(
+            conversation
+                .expiration_timestamp
+                .as_ref()
+                .and_then(|timestamp| timestamp.unix_seconds()),
+            Some(1683315871)
+        );
+        assert!(sent.pricing.as_ref().unwrap().billable);
+        assert!(sent.errors.is_empty());
+
+        let failed = &statuses[1];
+        assert_eq!(failed.status, Status::Failed);
+        assert_eq!(failed.errors[0].code, 131047);
+        assert!(
+            failed.errors[0]
+                .error_data
+                .as_ref()
+                .unwrap()
+                .details
+                .contains("24 hours")
+        );
+    }
+
+    #[test]
+    fn incoming_template_events() {
+        use formatter::incoming_type::{event::EventPayload, template::TemplateStatus};
+
+        let payload = r#"
+{
+    "object": "whatsapp_business_account",
+    "entry": [
+        {
+            "id": "whatsapp_business_id",
+            "time": 1683229471,
+            "changes": [
+                {
+                    "value": {
+                        "event": "PAUSED",
+                        "message_template_id": 594425479261596,
+                        "message_template_name": "order_update",
+                        "message_template_language": "en_US",
+                        "reason": "NONE",
+                        "other_info": {
+                            "title": "FIRST_PAUSE",
+                            "description": "Your template has been paused for 3 hours."
+                        }
+                    },
+                    "field": "message_template_status_update"
+                },
+                {
+                    "value": {
+                        "previous_quality_score": "GREEN",
+                        "new_quality_score": "YELLOW",
+                        "message_template_id": 594425479261596,
+                        "message_template_name": "order_update",
+                        "message_template_language": "en_US"
+                    },
+                    "field": "message_template_quality_update"
+                },
+                {
+                    "value": {
+                        "message_template_id": 594425479261596,
+                        "message_template_name": "order_update",
+                        "message_template_language": "en_US",
+                        "previous_category": "UTILITY",
+                        "new_category": "MARKETING"
+                    },
+                    "field": "template_category_update"
+                },
+                {
+                    "value": {
+                        "event": "PAUSED"
+                    },
+                    "field": "message_template_status_update"
+                }
+            ]
+        }
+    ]
+}
+"#;
+
+        let (events, errors) = fixture_config().incoming(payload).unwrap();
+        assert_eq!(events.len(), 3);
+
+        let EventPayload::TemplateStatus(status) = &events[0].payload else {
+            panic!(
+                "expected a template status update, got {:?}",
+                events[0].payload
+            );
+        };
+        assert_eq!(status.event, TemplateStatus::Paused);
+        assert_eq!(status.message_template_id, 594425479261596);
+        assert_eq!(status.other_info.as_ref().unwrap().title, "FIRST_PAUSE");
+
+        let EventPayload::TemplateQuality(quality) = &events[1].payload else {
+            panic!(
+                "expected a template quality update, got {:?}",
+                events[1].payload
+            );
+        };
+        assert_eq!(quality.new_quality_score, "YELLOW");
+
+        let EventPayload::TemplateCategory(category) = &events[2].payload else {
+            panic!(
+                "expected a template category update, got {:?}",
+                events[2].payload
+            );
+        };
+        assert_eq!(category.new_category.as_deref(), Some("MARKETING"));
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "entry[0].changes[3].value");
+    }
+
+    #[test]
+    fn incoming_account_events() {
+        use formatter::incoming_type::{account::QualityEvent, event::EventPayload};
+
+        let payload = r#"
+{
+    "object": "whatsapp_business_account",
+    "entry": [
+        {
+            "id": "whatsapp_business_id",
+            "time": 1683229471,
+            "changes": [
+                {
+                    "value": {
+                        "display_phone_number": "15550783881",
+                        "event": "DOWNGRADE",
+                        "current_limit": "TIER_250",
+                        "old_limit": "TIER_1K"
+                    },
+                    "field": "phone_number_quality_update"
+                },
+                {
+                    "value": {
+                        "phone_number": "15550783881",
+                        "event": "ACCOUNT_VIOLATION",
+                        "violation_info": {
+                            "violation_type": "SCAM"
+                        },
+                        "restriction_info": [
+                            {
+                                "restriction_type": "RESTRICTED_BIZ_INITIATED_MESSAGING",
+                                "expiration": "1683488671"
+                            }
+                        ]
+                    },
+                    "field": "account_update"
+                },
+                {
+                    "value": {
+                        "entity_type": "PHONE_NUMBER",
+                        "entity_id": 106540352242922,
+                        "alert_info": {
+                            "alert_severity": "WARNING",
+                            "alert_status": "ACTIVE",
+                            "alert_type": "OBA_APPROVED",
+                            "alert_description": "Your request for Official Business Account status was approved."
+                        }
+                    },
+                    "field": "account_alerts"
+                }
+            ]
+        }
+    ]
+}
+"#;
+
+        let (events, errors) = fixture_config().incoming(payload).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(events.len(), 3);
+
+        let EventPayload::PhoneNumberQuality(quality) = &events[0].payload else {
+            panic!(
+                "expected a phone number quality update, got {:?}",
+                events[0].payload
+            );
+        };
+        assert_eq!(quality.event, QualityEvent::Downgrade);
+        assert_eq!(quality.current_limit, "TIER_250");
+        assert_eq!(quality.old_limit.as_deref(), Some("TIER_1K"));
+        assert!(quality.is_degraded());
+
+        let EventPayload::AccountUpdate(update) = &events[1].payload else {
+            panic!("expected an account update, got {:?}", events[1].payload);
+        };
+        assert_eq!(update.event, "ACCOUNT_VIOLATION");
+        assert_eq!(
+            update.violation_info.as_ref().unwrap().violation_type,
+            "SCAM"
+        );
+        assert_eq!(update.restriction_info.len(), 1);
+        assert!(update.ban_info.is_none());
+
+        let EventPayload::AccountAlert(alert) = &events[2].payload else {
+            panic!("expected an account alert, got {:?}", events[2].payload);
+        };
+        assert_eq!(alert.alert_info.alert_severity, "WARNING");
+        assert_eq!(alert.alert_info.alert_type, "OBA_APPROVED");
+        assert_eq!(alert.entity_type, "PHONE_NUMBER");
+        assert_eq!(alert.entity_id, "106540352242922");
+    }
+
+    #[test]
+    fn tenant_registry_routing() {
+        use error::DiagnosticKind;
+        use formatter::incoming_type::event::EventPayload;
+        use tenant::TenantRegistry;
+
+        let tenant = |business_id: &str, phone_number_id: &str| {
+            config::Config::from(
+                String::from("https://graph.facebook.com"),
+                String::from("v17.0"),
+                business_id.to_string(),
+                phone_number_id.to_string(),
+                format!("token-{}", phone_number_id),
+            )
+        };
+        let mut registry: TenantRegistry = vec![
+            tenant("business_a", "phone_a1"),
+            tenant("business_a", "phone_a2"),
+            tenant("business_b", "phone_b1"),
+        ]
+        .into_iter()
+        .collect();
+
+        let change = |phone_number_id: &str| {
+            format!(
+                r#"{{
+                    "value": {{
+                        "messaging_product": "whatsapp",
+                        "metadata": {{
+                            "display_phone_number": "15550783881",
+                            "phone_number_id": "{}"
+                        }},
+                        "messages": [
+                            {{
+                                "from": "16315551234",
+                                "id": "wamid.{}",
+                                "timestamp": "1683229471",
+                                "text": {{
+                                    "body": "hello"
+                                }},
+                                "type": "text"
+                            }}
+                        ]
+                    }},
+                    "field": "messages"
+                }}"#,
+                phone_number_id, phone_number_id
+            )
+        };
+        let payload = format!(
+            r#"{{
+    "object": "whatsapp_business_account",
+    "entry": [
+        {{ "id": "business_a", "changes": [{}, {}, {}] }},
+        {{ "id": "business_b", "changes": [{}] }},
+        {{ "id": "business_c", "changes": [{}] }},
+        {{
+            "id": "business_b",
+            "changes": [
+                {{
+                    "value": {{
+                        "event": "APPROVED",
+                        "message_template_id": 1,
+                        "message_template_name": "welcome",
+                        "message_template_language": "en_US"
+                    }},
+                    "field": "message_template_status_update"
+                }}
+            ]
+        }},
+        {{
+            "id": "business_a",
+            "changes": [
+                {{
+                    "value": {{
+                        "entity_type": "PHONE_NUMBER",
+                        "entity_id": "phone_a2",
+                        "alert_info": {{
+                            "alert_severity": "CRITICAL",
+                            "alert_status": "ACTIVE",
+                            "alert_type": "PHONE_NUMBER_RESTRICTED",
+                            "alert_description": "Messaging is restricted."
+                        }}
+                    }},
+                    "field": "account_alerts"
+                }}
+            ]
+        }}
+    ]
+}}"#,
+            change("phone_a1"),
+            change("phone_a2"),
+            change("phone_b1"),
+            change("phone_b1"),
+            change("phone_a1"),
+        );
+
+        let (events, errors) = registry.incoming(&payload).unwrap();
+        assert_eq!(events.len(), 5);
+
+        let resolved: Vec<&str> = events
+            .iter()
+            .map(|event| {
+                registry
+                    .resolve(event)
+                    .unwrap()
+                    .whatsapp_phone_number_id
+                    .as_str()
+            })
+            .collect();
+        assert_eq!(
+            resolved,
+            ["phone_a1", "phone_a2", "phone_b1", "phone_b1", "phone_a2"]
+        );
+        assert!(matches!(events[4].payload, EventPayload::AccountAlert(_)));
+        assert!(matches!(events[3].payload, EventPayload::TemplateStatus(_)));
+        assert_eq!(
+            registry
+                .resolve(&events[1])
+                .unwrap()
+                .whatsapp_system_user_token,
+            "token-phone_a2"
+        );
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].path, "entry[0].changes[2]");
+        assert_eq!(
+            errors[0].kind,
+            DiagnosticKind::UnknownPhoneNumber(String::from("phone_b1"))
+        );
+        assert_eq!(errors[1].path, "entry[2]");
+        assert_eq!(
+            errors[1].kind,
+            DiagnosticKind::UnknownBusinessAccount(String::from("business_c"))
+        );
+
+        // Moving a phone number to another business account updates the business index.
+        let previous = registry.insert(tenant("business_c", "phone_a1")).unwrap();
+        assert_eq!(previous.whatsapp_business_id, "business_a");
+        assert_eq!(
+            registry
+                .get_by_business("business_a")
+                .unwrap()
+                .whatsapp_phone_number_id,
+            "phone_a2"
+        );
+        registry.insert(tenant("business_c", "phone_b1"));
+        assert!(registry.get_by_business("business_b").is_none());
+        assert_eq!(
+            registry
+                .get_by_business("business_c")
+                .unwrap()
+                .whatsapp_system_user_token,
+            "token-phone_a1"
+        );
+        assert_eq!(registry.len(), 3);
+
+        let (events, errors) = registry.incoming(&payload).unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(errors[0].path, "entry[0].changes[0]");
+        assert_eq!(
+            errors[0].kind,
+            DiagnosticKind::UnknownPhoneNumber(String::from("phone_a1"))
+        );
+        assert!(errors.iter().any(|error| error.kind
+            == DiagnosticKind::UnknownBusinessAccount(String::from("business_b"))));
+    }
+
+    #[test]
+    fn incoming_sender_and_metadata() {
+        let payload = messages_fixture(
+            r#"[
+            {
+                "from": "16315551234",
+                "id": "wamid.text",
+                "timestamp": "1683229471",
+                "text": {
+                    "body": "hello"
+                },
+                "type": "text"
+            },
+            {
+                "from": "16315551234",
+                "id": "wamid.reaction",
+                "timestamp": "1683229472",
+                "reaction": {
+                    "message_id": "wamid.text",
+                    "emoji": "👍"
+                },
+                "type": "reaction"
+            }
+        ]"#,
+        );
+
+        let (messages, errors) = fixture_config().incoming_message(&payload).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(messages.len(), 2);
+
+        for message in &messages {
+            assert_eq!(message.sender_name(), Some("Jane"));
+            assert_eq!(message.sender().unwrap().wa_id, "16315551234");
+
+            let metadata = message.metadata().unwrap();
+            assert_eq!(metadata.display_phone_number, "15550783881");
+            assert_eq!(metadata.phone_number_id, "whatsapp_phone_number_id");
+        }
+
+        // A contact without a profile does not hold back the messages.
+        let without_profile = payload.replace(r#""profile""#, r#""unexpected""#);
+        let (messages, errors) = fixture_config().incoming_message(&without_profile).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].sender().unwrap().wa_id, "16315551234");
+        assert_eq!(messages[0].sender_name(), None);
+    }
+
+    #[test]
+    fn incoming_media_messages() {
+        use formatter::incoming_type::IncomingMessage;
+
+        let payload = messages_fixture(
+            r#"[
+            {
+                "from": "16315551234",
+                "id": "wamid.image",
+                "timestamp": "1683229471",
+                "type": "image",
+                "image": {
+                    "caption": "Receipt",
+                    "mime_type": "image/jpeg",
+                    "sha256": "image_hash",
+                    "id": "image_id"
+                }
+            },
+            {
+                "from": "16315551234",
+                "id": "wamid.video",
+                "timestamp": "1683229472",
+                "type": "video",
+                "video": {
+                    "mime_type": "video/mp4",
+                    "sha256": "video_hash",
+                    "id": "video_id"
+                }
+            },
+            {
+                "from": "16315551234",
+                "id": "wamid.audio",
+                "timestamp": "1683229473",
+                "type": "audio",
+                "audio": {
+                    "mime_type": "audio/ogg; codecs=opus",
+                    "sha256": "audio_hash",
+                    "id": "audio_id",
+                    "voice": true
+                }
+            },
+            {
+                "from": "16315551234",
+                "id": "wamid.document",
+                "timestamp": "1683229474",
+                "type": "document",
+                "document": {
+                    "caption": "Invoice",
+                    "filename": "invoice.pdf",
+                    "mime_type": "application/pdf",
+                    "sha256": "document_hash",
+                    "id": "document_id"
+                }
+            },
+            {
+                "from": "16315551234",
+                "id": "wamid.sticker",
+                "timestamp": "1683229475",
+                "type": "sticker",
+                "sticker": {
+                    "mime_type": "image/webp",
+                    "sha256": "sticker_hash",
+                    "id": "sticker_id",
+                    "animated": true
+                }
+            }
+        ]"#,
+        );
+
+        let (messages, errors) = fixture_config().incoming_message(&payload).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(messages.len(), 5);
+
+        let IncomingMessage::Image(image) = &messages[0] else {
+            panic!("expected an image, got {:?}", messages[0]);
+        };
+        assert_eq!(image.image.id, "image_id");
+        assert_eq!(image.image.caption.as_deref(), Some("Receipt"));
+
+        let IncomingMessage::Video(video) = &messages[1] else {
+            panic!("expected a video, got {:?}", messages[1]);
+        };
+        assert_eq!(video.video.id, "video_id");
+        assert_eq!(video.video.mime_type, "video/mp4");
+        assert!(video.video.caption.is_none());
+
+        let IncomingMessage::Audio(audio) = &messages[2] else {
+            panic!("expected an audio, got {:?}", messages[2]);
+        };
+        assert_eq!(audio.audio.id, "audio_id");
+        assert!(audio.audio.voice);
+
+        let IncomingMessage::Document(document) = &messages[3] else {
+            panic!("expected a document, got {:?}", messages[3]);
+        };
+        assert_eq!(document.document.id, "document_id");
+        assert_eq!(document.document.filename.as_deref(), Some("invoice.pdf"));
+        assert_eq!(document.document.caption.as_deref(), Some("Invoice"));
+
+        let IncomingMessage::Sticker(sticker) = &messages[4] else {
+            panic!("expected a sticker, got {:?}", messages[4]);
+        };
+        assert_eq!(sticker.sticker.id, "sticker_id");
+        assert!(sticker.sticker.animated);
+    }
+
+    #[test]
+    fn incoming_location_messages() {
+        use error::DiagnosticKind;
+        use formatter::incoming_type::IncomingMessage;
+
+        let payload = messages_fixture(
+            r#"[
+            {
+                "from": "16315551234",
+                "id": "wamid.place",
+                "timestamp": "1683229471",
+                "type": "location",
+                "location": {
+                    "latitude": 6.4281,
+                    "longitude": 3.4219,
+                    "name": "Lekki Conservation Centre",
+                    "address": "Lekki-Epe Expressway, Lagos",
+                    "url": "https://example.com"
+                }
+            },
+            {
+                "from": "16315551234",
+                "id": "wamid.pin",
+                "timestamp": "1683229472",
+                "type": "location",
+                "location": {
+                    "latitude": 6.4541,
+                    "longitude": 3.3947
+                }
+            },
+            {
+                "from": "16315551234",
+                "id": "wamid.broken",
+                "timestamp": "1683229473",
+                "type": "location",
+                "location": {
+                    "name": "Nowhere"
+                }
+            }
+        ]"#,
+        );
+
+        let (messages, errors) = fixture_config().incoming_message(&payload).unwrap();
+        assert_eq!(messages.len(), 2);
+
+        let IncomingMessage::Location(place) = &messages[0] else {
+            panic!("expected a location, got {:?}", messages[0]);
+        };
+        assert_eq!(place.location.latitude, 6.4281);
+        assert_eq!(place.location.longitude, 3.4219);
+        assert_eq!(
+            place.location.name.as_deref(),
+            Some("Lekki Conservation Centre")
+        );
+        assert_eq!(place.location.url.as_deref(), Some("https://example.com"));
+
+        let IncomingMessage::Location(pin) = &messages[1] else {
+            panic!("expected a location, got {:?}", messages[1]);
+        };
+        assert!(pin.location.name.is_none());
+        assert!(pin.location.address.is_none());
+
+        // Lekki Conservation Centre to Lagos Island is roughly 4.3 km.
+        let distance = place
+            .location
+            .distance_to(pin.location.latitude, pin.location.longitude);
+        assert!((4_000.0..4_600.0).contains(&distance), "{}", distance);
+        assert_eq!(pin.location.distance_to(6.4541, 3.3947), 0.0);
+        assert!(place.location.is_within(6.4541, 3.3947, 5_000.0));
+        assert!(!place.location.is_within(6.4541, 3.3947, 1_000.0));
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "entry[0].changes[0].value.messages[2]");
+        assert!(matches!(errors[0].kind, DiagnosticKind::Invalid(_)));
+    }
+
+    #[test]
+    fn incoming_typed_timestamps() {
+        use formatter::incoming_type::timestamp::Timestamp;
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let payload = messages_fixture(
+            r#"[
+            {
+                "from": "16315551234",
+                "id": "wamid.second",
+                "timestamp": "1683229472",
+                "text": {
+                    "body": "second"
+                },
+                "type": "text"
+            },
+            {
+                "from": "16315551234",
+                "id": "wamid.first",
+                "timestamp": 1683229471,
+                "text": {
+                    "body": "first"
+                },
+                "type": "text"
+            },
+            {
+                "from": "16315551234",
+                "id": "wamid.malformed",
+                "timestamp": "yesterday",
+                "text": {
+                    "body": "malformed"
+                },
+                "type": "text"
+            }
+        ]"#,
+        );
+
+        let (messages, errors) = fixture_config().incoming_message(&payload).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let timestamps: Vec<&Timestamp> = messages
+            .iter()
+            .map(|message| match message {
+                formatter::incoming_type::IncomingMessage::Text(text) => &text.timestamp,
+                other => panic!("expected a text message, got {:?}", other),
+            })
+            .collect();
+
+        assert_eq!(timestamps[0].unix_seconds(), Some(1683229472));
+        assert_eq!(
+            timestamps[0].to_system_time(),
+            Some(UNIX_EPOCH + Duration::from_secs(1683229472))
+        );
+        assert_eq!(timestamps[1].as_str(), "1683229471");
+        assert!(timestamps[1] < timestamps[0]);
+
+        assert!(!timestamps[2].is_valid());
+        assert_eq!(timestamps[2].as_str(), "yesterday");
+        assert!(timestamps[2].to_system_time().is_none());
+        assert!(timestamps[2] < timestamps[1]);
+
+        assert_eq!(Timestamp::from_unix(1683229471), *timestamps[1]);
+        assert_eq!(
+            serde_json::to_value(timestamps[0]).unwrap(),
+            serde_json::json!("1683229472")
+        );
+    }
+
+    #[test]
+    fn incoming_unrecognized_messages() {
+        use formatter::incoming_type::IncomingMessage;
+
+        let payload = messages_fixture(
+            r#"[
+            {
+                "from": "16315551234",
+                "id": "wamid.poll",
+                "timestamp": "1683229471",
+                "type": "poll",
+                "poll": {
+                    "question": "Lunch?",
+                    "options": ["Pizza", "Salad"]
+                }
+            },
+            {
+                "from": "16315551234",
+                "id": "wamid.carousel",
+                "timestamp": "1683229472",
+                "type": "interactive",
+                "interactive": {
+                    "type": "carousel_reply",
+                    "carousel_reply": {
+                        "id": "card_1"
+                    }
+                }
+            },
+            {
+                "from": "16315551234",
+                "id": "wamid.unsupported",
+                "timestamp": "1683229473",
+                "type": "unsupported",
+                "errors": [
+                    {
+                        "code": 131051,
+                        "title": "Message type unknown",
+                        "message": "Message type unknown",
+                        "error_data": {
+                            "details": "Message type is currently not supported."
+                        }
+                    }
+                ]
+            }
+        ]"#,
+        );
+
+        let (messages, errors) = fixture_config().incoming_message(&payload).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(messages.len(), 3);
+
+        let IncomingMessage::Other(poll) = &messages[0] else {
+            panic!("expected an unrecognized message, got {:?}", messages[0]);
+        };
+        assert_eq!(poll.id, "wamid.poll");
+        assert_eq!(poll.from, "16315551234");
+        assert_eq!(poll.r#type, "poll");
+        assert_eq!(poll.timestamp.unix_seconds(), Some(1683229471));
+        assert_eq!(poll.raw["poll"]["question"], "Lunch?");
+        assert_eq!(messages[0].sender_name(), Some("Jane"));
+
+        let IncomingMessage::Other(carousel) = &messages[1] else {
+            panic!("expected an unrecognized message, got {:?}", messages[1]);
+        };
+        assert_eq!(carousel.r#type, "interactive");
+        assert_eq!(carousel.raw["interactive"]["type"], "carousel_reply");
+
+        let IncomingMessage::Unknown(unsupported) = &messages[2] else {
+            panic!("expected an unsupported message, got {:?}", messages[2]);
+        };
+        assert_eq!(unsupported.errors[0].code, 131051);
+        assert_eq!(
+            unsupported.errors[0].details,
+            "Message type is currently not supported."
+        );
+        assert_eq!(unsupported.raw["id"], "wamid.unsupported");
+        assert_eq!(unsupported.raw["type"], "unsupported");
+        assert_eq!(
+            unsupported.raw["errors"][0]["error_data"]["details"],
+            "Message type is currently not supported."
+        );
+    }
+
+    #[test]
+    fn whatsapp_client_is_shareable() {
+        use client::WhatsAppClient;
+        use std::time::Duration;
+
+        fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+        assert_shareable::<WhatsAppClient>();
+
+        let client = WhatsAppClient::builder(fixture_config())
+            .timeout(Duration::from_secs(10))
+            .connect_timeout(Duration::from_secs(2))
+            .proxy(reqwest::Proxy::all("http://127.0.0.1:3128").unwrap())
+            .user_agent("whatsapp-handler-tests")
+            .build()
+            .unwrap();
+
+        let clone = client.clone();
+        assert_eq!(
+            clone.config().whatsapp_phone_number_id,
+            "whatsapp_phone_number_id"
+        );
+        assert!(std::ptr::eq(client.config(), clone.config()));
+    }
+
+    #[test]
+    fn outgoing_send_errors() {
+        use action::outgoing::decode_response;
+        use error::{ErrorCode, SendError};
+        use reqwest::{StatusCode, header::HeaderMap};
+
+        let ok = decode_response(
+            StatusCode::OK,
+            &HeaderMap::new(),
+            r#"{"messaging_product":"whatsapp","messages":[{"id":"wamid.sent"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(ok.message_id(), Some("wamid.sent"));
+
+        let window = decode_response(
+            StatusCode::BAD_REQUEST,
+            &HeaderMap::new(),
+            r#"{
+                "error": {
+                    "message": "(#131047) Re-engagement message",
+                    "type": "OAuthException",
+                    "code": 131047,
+                    "error_data": {
+                        "messaging_product": "whatsapp",
+                        "details": "Message failed to send because more than 24 hours have passed since the customer last replied to this number."
+                    },
+                    "fbtrace_id": "Az8or2yhqkZfEZ-_4Qn_Bam"
+                }
+            }"#,
+        )
+        .unwrap_err();
+        let api_error = window.api_error().unwrap();
+        assert_eq!(api_error.kind(), ErrorCode::ReEngagementWindow);
+        assert_eq!(api_error.r#type, "OAuthException");
+        assert_eq!(
+            api_error.fbtrace_id.as_deref(),
+            Some("Az8or2yhqkZfEZ-_4Qn_Bam")
+        );
+        assert!(api_error.details().unwrap().starts_with("Message failed"));
+        assert!(!window.is_retryable());
+
+        let throttled = decode_response(
+            StatusCode::BAD_REQUEST,
+            &HeaderMap::new(),
+            r#"{"error":{"message":"(#130429) Rate limit hit","type":"OAuthException","code":130429,"error_subcode":2494055}}"#,
+        )
+        .unwrap_err();
+        assert_eq!(throttled.api_error().unwrap().error_subcode, Some(2494055));
+        assert!(throttled.is_retryable());
+
+        // An error object is a failure even when it comes with a 200.
+        assert!(matches!(
+            decode_response(
+                StatusCode::OK,
+                &HeaderMap::new(),
+                r#"{"error":{"message":"Template paused","code":132015}}"#
+            ),
+            Err(SendError::Api { status: 200, .. })
+        ));
+
+        let gateway = decode_response(
+            StatusCode::BAD_GATEWAY,
+            &HeaderMap::new(),
+            "<html>Bad Gateway</html>",
+        )
+        .unwrap_err();
+        assert!(matches!(gateway, SendError::Http { status: 502, .. }));
+        assert!(gateway.is_retryable());
+
+        let forbidden = decode_response(StatusCode::FORBIDDEN, &HeaderMap::new(), "").unwrap_err();
+        assert!(!forbidden.is_retryable());
+
+        assert_eq!(ErrorCode::from(132015), ErrorCode::TemplatePaused);
+        assert_eq!(ErrorCode::from(131026).code(), 131026);
+        assert_eq!(ErrorCode::from(4242), ErrorCode::Other(4242));
+        assert!(ErrorCode::from(80007).is_retryable());
+    }
+
+    #[test]
+    fn outgoing_send_response() {
+        use action::outgoing::decode_response;
+        use error::SendError;
+        use formatter::outgoing_type::response::MessageStatus;
+        use reqwest::{StatusCode, header::HeaderMap};
+
+        let response = decode_response(
+            StatusCode::OK,
+            &HeaderMap::new(),
+            r#"{
+                "messaging_product": "whatsapp",
+                "contacts": [
+                    {
+                        "input": "+1 631-555-1234",
+                        "wa_id": "16315551234"
+                    }
+                ],
+                "messages": [
+                    {
+                        "id": "wamid.HBgLMTY1MDM4Nzk0MzkVAgARGBJDQjZCMzlEQUE4OTJBMTE4RTUA",
+                        "message_status": "accepted"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(response.messaging_product, "whatsapp");
+        assert_eq!(response.contacts[0].input, "+1 631-555-1234");
+        assert_eq!(response.wa_id(), Some("16315551234"));
+        assert_eq!(
+            response.message_id(),
+            Some("wamid.HBgLMTY1MDM4Nzk0MzkVAgARGBJDQjZCMzlEQUE4OTJBMTE4RTUA")
+        );
+        assert_eq!(
+            response.messages[0].message_status,
+            Some(MessageStatus::Accepted)
+        );
+
+        // The wamid is the id later reported by status webhooks.
+        let payload = format!(
+            r#"{{
+    "object": "whatsapp_business_account",
+    "entry": [
+        {{
+            "id": "whatsapp_business_id",
+            "changes": [
+                {{
+                    "value": {{
+                        "messaging_product": "whatsapp",
+                        "metadata": {{
+                            "display_phone_number": "15550783881",
+                            "phone_number_id": "whatsapp_phone_number_id"
+                        }},
+                        "statuses": [
+                            {{
+                                "id": "{}",
+                                "recipient_id": "16315551234",
+                                "status": "delivered",
+                                "timestamp": "1683229471"
+                            }}
+                        ]
+                    }},
+                    "field": "messages"
+                }}
+            ]
+        }}
+    ]
+}}"#,
+            response.message_id().unwrap()
+        );
+        let (statuses, _) = fixture_config().incoming_statuses(&payload).unwrap();
+        assert_eq!(Some(statuses[0].id.as_str()), response.message_id());
+
+        let unexpected =
+            decode_response(StatusCode::OK, &HeaderMap::new(), r#"{"success": true}"#).unwrap_err();
+        assert!(matches!(unexpected, SendError::Decode { .. }));
+        assert!(!unexpected.is_retryable());
+    }
+
+    #[test]
+    fn retry_policy_backoff() {
+        use retry::RetryPolicy;
+        use std::time::Duration;
+
+        let policy = RetryPolicy::default()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_secs(1))
+            .with_jitter(false);
+        assert_eq!(policy.delay(1, None), Some(Duration::from_millis(100)));
+        assert_eq!(policy.delay(2, None), Some(Duration::from_millis(200)));
+        assert_eq!(policy.delay(3, None), Some(Duration::from_millis(400)));
+        assert_eq!(policy.delay(10, None), Some(Duration::from_secs(1)));
+        assert_eq!(policy.delay(u32::MAX, None), Some(Duration::from_secs(1)));
+
+        // A Retry-After hint wins; one longer than the maximum backoff stops retrying.
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_millis(700))),
+            Some(Duration::from_millis(700))
+        );
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(60))), None);
+
+        let jittered = policy.clone().with_jitter(true);
+        for attempt in 1..5 {
+            let delay = jittered.delay(attempt, None).unwrap();
+            let backoff = policy.delay(attempt, None).unwrap();
+            assert!(delay <= backoff && delay >= backoff / 2, "{:?}", delay);
+        }
+
+        assert_eq!(RetryPolicy::none().max_attempts(), 1);
+        assert_eq!(
+            RetryPolicy::default().with_max_attempts(0).max_attempts(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn outgoing_retries_transient_failures() {
+        use error::{ErrorCode, SendError};
+        use retry::RetryPolicy;
+        use std::sync::atomic::Ordering;
+        use std::time::Duration;
+
+        let policy = RetryPolicy::default()
+            .with_max_attempts(3)
+            .with_initial_backoff(Duration::from_millis(1));
+        let config = |base_url: String| {
+            config::Config::from(
+                base_url,
+                String::from("v17.0"),
+                String::from("whatsapp_business_id"),
+                String::from("whatsapp_phone_number_id"),
+                String::from("token"),
+            )
+            .with_retry_policy(policy.clone())
+        };
+
+        // Throttled, then a server error, then accepted.
+        let (base_url, requests) = mock_graph_api(vec![
+            (
+                429,
+                "Retry-After: 0\r\n",
+                r#"{"error":{"message":"(#130429) Rate limit hit","type":"OAuthException","code":130429}}"#,
+            ),
+            (503, "", "Service Unavailable"),
+            (
+                200,
+                "",
+                r#"{"messaging_product":"whatsapp","contacts":[{"input":"16315551234","wa_id":"16315551234"}],"messages":[{"id":"wamid.retried"}]}"#,
+            ),
+        ])
+        .await;
+        let response = config(base_url).outgoing(text_message()).await.unwrap();
+        assert_eq!(response.message_id(), Some("wamid.retried"));
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+        // Permanent errors are returned at once.
+        let (base_url, requests) = mock_graph_api(vec![(
+            400,
+            "",
+            r#"{"error":{"message":"(#131047) Re-engagement message","type":"OAuthException","code":131047}}"#,
+        )])
+        .await;
+        let error = config(base_url).outgoing(text_message()).await.unwrap_err();
+        assert_eq!(
+            error.api_error().unwrap().kind(),
+            ErrorCode::ReEngagementWindow
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // The last error is returned once every attempt has failed.
+        let (base_url, requests) = mock_graph_api(vec![
+            (500, "", "Internal Server Error"),
+            (500, "", "Internal Server Error"),
+        ])
+        .await;
+        let client = client::WhatsAppClient::builder(config(base_url))
+            .retry_policy(policy.clone().with_max_attempts(2))
+            .build()
+            .unwrap();
+        let error = client.send(&text_message()).await.unwrap_err();
+        assert!(matches!(error, SendError::Http { status: 500, .. }));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // A Retry-After longer than the maximum backoff is not cut short.
+        let (base_url, requests) = mock_graph_api(vec![(
+            429,
+            "Retry-After: 60\r\n",
+            r#"{"error":{"message":"(#130429) Rate limit hit","type":"OAuthException","code":130429}}"#,
+        )])
+        .await;
+        let error = config(base_url).outgoing(text_message()).await.unwrap_err();
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(60)));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // A timeout may mean the message was sent, so it is not sent again.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let connections = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let accepted = connections.clone();
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                sockets.push(socket);
+            }
+        });
+        let client = client::WhatsAppClient::builder(config(base_url))
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let error = client.send(&text_message()).await.unwrap_err();
+        assert!(matches!(&error, SendError::Transport(error) if error.is_timeout()));
+        assert!(!error.is_retryable());
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limiter_paces_sends() {
+        use rate_limit::RateLimiter;
+        use std::time::Duration;
+        use tokio::time::Instant;
+
+        // Two messages per second with no burst: every send after the first waits 500 ms.
+        let limiter = RateLimiter::new(2).with_burst(1);
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire("phone_a", "16315551234").await;
+        }
+        assert_eq!(start.elapsed(), Duration::from_millis(1000));
+
+        // Another phone number has its own bucket.
+        let start = Instant::now();
+        limiter.acquire("phone_b", "16315551234").await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        // Clones share the buckets.
+        let start = Instant::now();
+        limiter.clone().acquire("phone_b", "16315559876").await;
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+
+        // Messages to the same recipient are spaced apart; other recipients are not held up.
+        let limiter = RateLimiter::new(100).with_recipient_interval(Duration::from_secs(6));
+        let start = Instant::now();
+        limiter.acquire("phone_a", "16315551234").await;
+        limiter.acquire("phone_a", "16315559876").await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        limiter.acquire("phone_a", "16315551234").await;
+        assert_eq!(start.elapsed(), Duration::from_secs(6));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limiter_paces_recipients_independently() {
+        use rate_limit::RateLimiter;
+        use std::time::Duration;
+        use tokio::time::Instant;
+
+        // A queue for one recipient does not hold up sends to another.
+        let limiter = RateLimiter::new(80).with_recipient_interval(Duration::from_secs(6));
+        let start = Instant::now();
+        let queued: Vec<_> = (0..10)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move { limiter.acquire("phone_a", "16315551234").await })
+            })
+            .collect();
+        tokio::task::yield_now().await;
+        limiter.acquire("phone_a", "16315559876").await;
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        for task in queued {
+            task.await.unwrap();
+        }
+        assert_eq!(start.elapsed(), Duration::from_secs(54));
+
+        // Held-back sends spend the phone number's token when they go out, so they never
+        // fire alongside a full burst for another recipient.
+        let limiter = RateLimiter::new(1)
+            .with_burst(1)
+            .with_recipient_interval(Duration::from_secs(6));
+        let start = Instant::now();
+        let queued: Vec<_> = (0..3)
+            .map(|_| {
+                let limiter = limiter.clone();
+                tokio::spawn(async move {
+                    limiter.acquire("phone_a", "16315551234").await;
+                    start.elapsed()
+                })
+            })
+            .collect();
+
+        tokio::time::sleep(Duration::from_secs(6)).await;
+        limiter.acquire("phone_a", "16315559876").await;
+        let other = start.elapsed();
+        assert!(other <= Duration::from_secs(7), "{:?}", other);
+
+        let mut recipient = Vec::new();
+        for task in queued {
+            recipient.push(task.await.unwrap());
+        }
+        recipient.sort();
+        for pair in recipient.windows(2) {
+            assert!(
+                pair[1] - pair[0] >= Duration::from_secs(6),
+                "{:?}",
+                recipient
+            );
+        }
+        let mut sent = recipient.clone();
+        sent.push(other);
+        sent.sort();
+        for pair in sent.windows(2) {
+            assert!(pair[1] - pair[0] >= Duration::from_secs(1), "{:?}", sent);
+        }
+    }
+
+    #[tokio::test]
+    async fn outgoing_waits_for_rate_limiter() {
+        use rate_limit::RateLimiter;
+        use std::sync::atomic::Ordering;
+        use std::time::{Duration, Instant};
+
+        let accepted = r#"{"messaging_product":"whatsapp","messages":[{"id":"wamid.paced"}]}"#;
+        let (base_url, requests) = mock_graph_api(vec![
+            (200, "", accepted),
+            (200, "", accepted),
+            (200, "", accepted),
+        ])
+        .await;
+        let config = config::Config::from(
+            base_url,
+            String::from("v17.0"),
+            String::from("whatsapp_business_id"),
+            String::from("whatsapp_phone_number_id"),
+            String::from("token"),
+        )
+        .with_rate_limiter(RateLimiter::new(20).with_burst(1));
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            let response = config.outgoing(text_message()).await.unwrap();
+            assert_eq!(response.message_id(), Some("wamid.paced"));
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
 }
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Recipient slots kept before expired ones are pruned.
+const RECIPIENT_PRUNE_THRESHOLD: usize = 1024;
+
+/// Client-side rate limiter for outgoing sends.
+///
+/// Each `whatsapp_phone_number_id` gets its own token bucket, so one busy number does not
+/// slow down the others. Optionally, messages to the same recipient from the same number
+/// are spaced apart, to stay under the per-pair limit (error `131056`).
+///
+/// Sends over the limit wait for their turn instead of failing. The limiter is cheap to
+/// clone; clones share the same buckets, so one limiter can be set on several `Config`s.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use whatsapp_handler::rate_limit::RateLimiter;
+///
+/// let limiter = RateLimiter::new(80)
+///     .with_burst(10)
+///     .with_recipient_interval(Duration::from_secs(6));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    messages_per_second: f64,
+    burst: f64,
+    recipient_interval: Option<Duration>,
+    state: Arc<Mutex<LimiterState>>,
+}
+
+/// Buckets and recipient slots shared by the clones of a `RateLimiter`.
+#[derive(Debug, Default)]
+struct LimiterState {
+    /// Token buckets keyed by `phone_number_id`.
+    buckets: HashMap<String, Bucket>,
+
+    /// Pacing state keyed by `(phone_number_id, recipient)`.
+    recipients: HashMap<(String, String), RecipientSlot>,
+}
+
+/// Token bucket of one phone number. `tokens` goes negative when sends are queued.
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Pacing state of one `(phone_number_id, recipient)` pair.
+#[derive(Debug)]
+struct RecipientSlot {
+    /// Slot handed to the next caller, so queued messages keep their order.
+    next_slot: Instant,
+    /// When the last message to the pair goes out.
+    last_sent: Option<Instant>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter allowing `messages_per_second` sends per phone number.
+    ///
+    /// The burst defaults to the same number; a value of `0` is treated as `1`.
+    pub fn new(messages_per_second: u32) -> Self {
+        let messages_per_second = f64::from(messages_per_second.max(1));
+        RateLimiter {
+            messages_per_second,
+            burst: messages_per_second,
+            recipient_interval: None,
+            state: Arc::default(),
+        }
+    }
+
+    /// Sets how many sends may go out at once before the rate applies. `0` is treated as `1`.
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = f64::from(burst.max(1));
+        self
+    }
+
+    /// Sets the minimum time between two messages to the same recipient.
+    pub fn with_recipient_interval(mut self, recipient_interval: Duration) -> Self {
+        self.recipient_interval = Some(recipient_interval);
+        self
+    }
+
+    /// Waits until a message from `phone_number_id` to `recipient` may be sent.
+    ///
+    /// The recipient slot is reserved first; the phone number's token is only spent once
+    /// that slot is due, so messages held back for one recipient do not delay the others.
+    /// Callers are served in the order they call this method.
+    pub async fn acquire(&self, phone_number_id: &str, recipient: &str) {
+        let mut ready_at = self.reserve_slot(phone_number_id, recipient, Instant::now());
+        loop {
+            if ready_at > Instant::now() {
+                tokio::time::sleep_until(ready_at).await;
+            }
+            match self.take_token(phone_number_id, recipient, Instant::now()) {
+                Ok(wait) => {
+                    if !wait.is_zero() {
+                        tokio::time::sleep(wait).await;
+                    }
+                    return;
+                }
+                Err(retry_at) => ready_at = retry_at,
+            }
+        }
+    }
+
+    /// Reserves the next slot for messages to `recipient` and returns when it is due.
+    fn reserve_slot(&self, phone_number_id: &str, recipient: &str, now: Instant) -> Instant {
+        let Some(interval) = self.recipient_interval else {
+            return now;
+        };
+        let mut state = self.lock();
+
+        if state.recipients.len() >= RECIPIENT_PRUNE_THRESHOLD {
+            state.recipients.retain(|_, slot| {
+                slot.next_slot > now || slot.last_sent.is_some_and(|sent| sent + interval > now)
+            });
+        }
+        let slot = state
+            .recipients
+            .entry((phone_number_id.to_string(), recipient.to_string()))
+            .or_insert(RecipientSlot {
+                next_slot: now,
+                last_sent: None,
+            });
+        let ready_at = slot.next_slot.max(now);
+        slot.next_slot = ready_at + interval;
+        ready_at
+    }
+
+    /// Spends a token of `phone_number_id` at `now` and returns how long to wait for it.
+    ///
+    /// Returns `Err` with the time to try again if the previous message to `recipient`
+    /// went out less than the recipient interval ago.
+    fn take_token(
+        &self,
+        phone_number_id: &str,
+        recipient: &str,
+        now: Instant,
+    ) -> Result<Duration, Instant> {
+        let mut state = self.lock();
+        let key = (phone_number_id.to_string(), recipient.to_string());
+
+        if let Some(interval) = self.recipient_interval
+            && let Some(last_sent) = state.recipients.get(&key).and_then(|slot| slot.last_sent)
+            && last_sent + interval > now
+        {
+            return Err(last_sent + interval);
+        }
+
+        let bucket = state
+            .buckets
+            .entry(phone_number_id.to_string())
+            .or_insert(Bucket {
+                tokens: self.burst,
+                updated: now,
+            });
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * self.messages_per_second;
+        bucket.tokens = (bucket.tokens + refill).min(self.burst) - 1.0;
+        bucket.updated = now;
+        let wait = if bucket.tokens < 0.0 {
+            Duration::from_secs_f64(-bucket.tokens / self.messages_per_second)
+        } else {
+            Duration::ZERO
+        };
+
+        if let Some(slot) = state.recipients.get_mut(&key) {
+            slot.last_sent = Some(now + wait);
+        }
+        Ok(wait)
+    }
+
+    /// Locks the shared state, recovering it if another thread panicked while holding it.
+    fn lock(&self) -> std::sync::MutexGuard<'_, LimiterState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// How failed sends are retried.
+///
+/// Only errors for which `SendError::is_retryable` returns `true` are retried. The delay
+/// before retry `n` is `initial_backoff * multiplier^(n - 1)`, capped at `max_backoff`,
+/// and then reduced by a random amount of up to half when jitter is enabled. A
+/// `Retry-After` header sent by the API replaces the computed delay; when it asks for
+/// longer than `max_backoff`, the send is not retried and the error is returned.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use whatsapp_handler::retry::RetryPolicy;
+///
+/// let policy = RetryPolicy::default()
+///     .with_max_attempts(5)
+///     .with_initial_backoff(Duration::from_millis(200));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, starting at 500 ms and doubling up to 30 s, with jitter.
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that sends each message once and never retries.
+    pub fn none() -> Self {
+        RetryPolicy::default().with_max_attempts(1)
+    }
+
+    /// Sets the total number of attempts, including the first one. `0` is treated as `1`.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    pub fn with_initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Sets the longest delay between two attempts.
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Sets the factor the delay grows by after each retry.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Enables or disables random jitter on the delay.
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns the total number of attempts, including the first one.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the delay to wait after failed attempt number `attempt` (starting at 1).
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - The attempt that just failed.
+    /// * `retry_after` - The delay asked for by the API, if any.
+    ///
+    /// # Returns
+    ///
+    /// The delay, or `None` if `retry_after` is longer than `max_backoff` and the send
+    /// should not be retried.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if let Some(retry_after) = retry_after {
+            return (retry_after <= self.max_backoff).then_some(retry_after);
+        }
+
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = Duration::from_secs_f64(
+            (self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent))
+                .min(self.max_backoff.as_secs_f64()),
+        );
+
+        if self.jitter {
+            Some(backoff.mul_f64(1.0 - random_fraction() / 2.0))
+        } else {
+            Some(backoff)
+        }
+    }
+}
+
+/// Returns a random number in `[0, 1)`, good enough to spread retries apart.
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,206 @@
+use crate::action::incoming;
+use crate::action::outgoing;
+use crate::config::Config;
+use crate::error::{Diagnostic, IncomingError, TenantError};
+use crate::formatter::incoming_type::event::{EventPayload, WebhookEvent};
+use crate::formatter::outgoing_type::{MessageType, response::SendResponse};
+use reqwest::Client;
+use std::collections::HashMap;
+
+/// A set of WhatsApp accounts served by a single webhook.
+///
+/// Each tenant is a `Config` for one phone number. Tenants are looked up by
+/// `phone_number_id`, and by business account id for events that carry no phone number
+/// (template and account webhooks). Outgoing sends of every tenant share one HTTP
+/// connection pool.
+#[derive(Debug, Default)]
+pub struct TenantRegistry {
+    /// Tenants keyed by `phone_number_id`.
+    tenants: HashMap<String, Config>,
+
+    /// Registered `phone_number_id`s of each business account, in registration order.
+    business_accounts: HashMap<String, Vec<String>>,
+
+    /// HTTP client shared by the sends of all tenants.
+    http: Client,
+}
+
+impl TenantRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a tenant and returns the registry, for chaining.
+    ///
+    /// # Example
+    /// ```
+    /// use whatsapp_handler::{config::Config, tenant::TenantRegistry};
+    ///
+    /// let registry = TenantRegistry::new()
+    ///     .with_tenant(Config::from(
+    ///         "https://graph.facebook.com".to_string(),
+    ///         "v17.0".to_string(),
+    ///         "business-a".to_string(),
+    ///         "phone-a".to_string(),
+    ///         "token-a".to_string(),
+    ///     ))
+    ///     .with_tenant(Config::from(
+    ///         "https://graph.facebook.com".to_string(),
+    ///         "v17.0".to_string(),
+    ///         "business-b".to_string(),
+    ///         "phone-b".to_string(),
+    ///         "token-b".to_string(),
+    ///     ));
+    ///
+    /// assert_eq!(registry.len(), 2);
+    /// ```
+    pub fn with_tenant(mut self, config: Config) -> Self {
+        self.insert(config);
+        self
+    }
+
+    /// Adds a tenant, replacing and returning any tenant with the same `phone_number_id`.
+    pub fn insert(&mut self, config: Config) -> Option<Config> {
+        let phone_number_id = config.whatsapp_phone_number_id.clone();
+        let business_id = config.whatsapp_business_id.clone();
+        let previous = self.tenants.insert(phone_number_id.clone(), config);
+
+        if let Some(previous) = &previous
+            && previous.whatsapp_business_id != business_id
+        {
+            self.unlink(&previous.whatsapp_business_id, &phone_number_id);
+        }
+        let phone_numbers = self.business_accounts.entry(business_id).or_default();
+        if !phone_numbers.contains(&phone_number_id) {
+            phone_numbers.push(phone_number_id);
+        }
+        previous
+    }
+
+    /// Removes a phone number from its business account's index.
+    fn unlink(&mut self, business_id: &str, phone_number_id: &str) {
+        if let Some(phone_numbers) = self.business_accounts.get_mut(business_id) {
+            phone_numbers.retain(|id| id != phone_number_id);
+            if phone_numbers.is_empty() {
+                self.business_accounts.remove(business_id);
+            }
+        }
+    }
+
+    /// Returns the tenant for a `phone_number_id`.
+    pub fn get(&self, phone_number_id: &str) -> Option<&Config> {
+        self.tenants.get(phone_number_id)
+    }
+
+    /// Returns a tenant of a business account.
+    ///
+    /// When several phone numbers share the account, the first one registered is returned.
+    pub fn get_by_business(&self, business_id: &str) -> Option<&Config> {
+        self.business_accounts
+            .get(business_id)
+            .and_then(|phone_numbers| phone_numbers.first())
+            .and_then(|phone_number_id| self.tenants.get(phone_number_id))
+    }
+
+    /// Returns the number of registered tenants.
+    pub fn len(&self) -> usize {
+        self.tenants.len()
+    }
+
+    /// Returns `true` if no tenant is registered.
+    pub fn is_empty(&self) -> bool {
+        self.tenants.is_empty()
+    }
+
+    /// Returns the tenant an incoming event belongs to.
+    ///
+    /// Events with metadata are resolved by `phone_number_id`, and phone number account
+    /// alerts by their `entity_id`. Other events are resolved by their entry id, to the
+    /// first tenant registered for the business account. Phone number quality and account
+    /// updates only name the display phone number, which a `Config` does not hold, so
+    /// treat the tenant returned for them as business-level credentials, not as the
+    /// number the event is about.
+    pub fn resolve(&self, event: &WebhookEvent) -> Option<&Config> {
+        if let Some(metadata) = &event.metadata {
+            return self.get(metadata.phone_number_id.trim().trim_matches('"'));
+        }
+        if let EventPayload::AccountAlert(alert) = &event.payload
+            && alert.entity_type == "PHONE_NUMBER"
+            && let Some(config) = self.get(&alert.entity_id)
+            && config.whatsapp_business_id == event.entry_id
+        {
+            return Some(config);
+        }
+        self.get_by_business(&event.entry_id)
+    }
+
+    /// Processes any incoming webhook payload for every registered tenant.
+    ///
+    /// Entries of unregistered business accounts, and changes for phone numbers that are
+    /// not registered under their entry's business account, are reported as diagnostics.
+    ///
+    /// # Arguments
+    /// - `payload`: Raw JSON string received from the WhatsApp webhook.
+    ///
+    /// # Returns
+    /// The same as `Config::incoming`. Use `resolve` to find the tenant of each event.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let (events, diagnostics) = registry.incoming(payload)?;
+    /// for event in events {
+    ///     let tenant = registry.resolve(&event).unwrap();
+    ///     println!("{}: {:?}", tenant.whatsapp_phone_number_id, event.payload);
+    /// }
+    /// ```
+    pub fn incoming(
+        &self,
+        payload: &str,
+    ) -> Result<(Vec<WebhookEvent>, Vec<Diagnostic>), IncomingError> {
+        incoming::collect_events(
+            payload,
+            |business_id| self.business_accounts.contains_key(business_id),
+            |business_id, phone_number_id| {
+                self.get(phone_number_id)
+                    .is_some_and(|config| config.whatsapp_business_id == business_id)
+            },
+        )
+    }
+
+    /// Sends an outgoing message with the credentials of the tenant owning `phone_number_id`.
+    ///
+    /// # Arguments
+    /// - `phone_number_id`: The phone number to send from.
+    /// - `message`: A structured `MessageType` representing the message to send.
+    ///
+    /// # Returns
+    /// A `Result` with:
+    /// - `Ok(SendResponse)`: The id of the sent message and the recipient's WhatsApp ID.
+    /// - `Err(TenantError)`: If the phone number is not registered or the request fails.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let response = registry.outgoing("phone-a", message).await?;
+    /// ```
+    pub async fn outgoing(
+        &self,
+        phone_number_id: &str,
+        message: MessageType,
+    ) -> Result<SendResponse, TenantError> {
+        let config = self
+            .get(phone_number_id)
+            .ok_or_else(|| TenantError::UnknownPhoneNumber(phone_number_id.to_string()))?;
+        Ok(outgoing::send_with(&self.http, config, &message).await?)
+    }
+}
+
+impl FromIterator<Config> for TenantRegistry {
+    fn from_iter<I: IntoIterator<Item = Config>>(iter: I) -> Self {
+        let mut registry = TenantRegistry::new();
+        for config in iter {
+            registry.insert(config);
+        }
+        registry
+    }
+}